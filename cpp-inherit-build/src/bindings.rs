@@ -0,0 +1,81 @@
+//! The base types' bindings, generated by bindgen from the same headers and flags as the vtables
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use cpp_inherit_core::metadata::HeaderMetadata;
+
+/// Name of the bindings file within `OUT_DIR`
+pub const BINDINGS_FILE: &str = "cpp-inherit-bindings.rs";
+
+pub(crate) fn generate(
+    headers: &[PathBuf],
+    flags: &[String],
+    classes: &[String],
+    metadata: &[HeaderMetadata],
+    out_dir: &Path,
+) {
+    let mut builder = bindgen::Builder::default()
+        .clang_args(["-x", "c++"])
+        .clang_args(flags);
+    for header in headers {
+        if header.extension() == Some("o".as_ref()) || header.extension() == Some("pdb".as_ref()) {
+            panic!(
+                "Bindings can only be generated from headers, not {}",
+                header.display()
+            );
+        }
+        builder = builder.header(header.display().to_string());
+    }
+    for class in classes {
+        builder = builder.allowlist_type(class);
+    }
+
+    let bindings = builder
+        .generate()
+        .unwrap_or_else(|error| panic!("Failed to generate bindings: {}", error))
+        .to_string();
+    check_vtable_fields(&bindings, metadata);
+
+    fs::write(out_dir.join(BINDINGS_FILE), bindings).unwrap_or_else(|error| {
+        panic!(
+            "Failed to write {}: {}",
+            out_dir.join(BINDINGS_FILE).display(),
+            error
+        )
+    });
+}
+
+/// `inherit_from_impl` expects a class with virtual methods to start with its vtable pointer,
+/// `vtable_`, or with the base class (`_base`) whose vtable pointer it shares
+fn check_vtable_fields(bindings: &str, metadata: &[HeaderMetadata]) {
+    let file = syn::parse_file(bindings).expect("bindgen generated invalid Rust");
+    for item in &file.items {
+        let item = match item {
+            syn::Item::Struct(item) => item,
+            _ => continue,
+        };
+        let has_vtable = metadata.iter().any(|header| {
+            header
+                .info
+                .get(&item.ident.to_string())
+                .is_some_and(|vtable| !vtable.is_empty())
+        });
+        if !has_vtable {
+            continue;
+        }
+
+        let first_field = item
+            .fields
+            .iter()
+            .next()
+            .and_then(|field| field.ident.as_ref());
+        if !matches!(first_field, Some(field) if field == "vtable_" || field == "_base") {
+            panic!(
+                "bindgen's `{}` doesn't start with its vtable pointer (`vtable_`) or base class \
+                 (`_base`)",
+                item.ident
+            );
+        }
+    }
+}
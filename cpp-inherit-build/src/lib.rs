@@ -0,0 +1,238 @@
+//! Precompute the vtables `cpp-inherit`'s macros need from a build script, so headers are
+//! compiled once with the right flags and cargo reruns the build script when they change.
+//!
+//! ```no_run
+//! // In build.rs's main
+//! cpp_inherit_build::Builder::new()
+//!     .header("src/test.hpp")
+//!     .flag("-Iinclude")
+//!     .generate();
+//! ```
+//!
+//! `#[inherit_from_impl]` then reads the vtables from the metadata file in `OUT_DIR`, and only
+//! compiles headers which aren't in it.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use cpp_inherit_core::metadata::{self, HeaderMetadata};
+
+#[cfg(feature = "bindgen")]
+mod bindings;
+#[cfg(feature = "bindgen")]
+pub use bindings::BINDINGS_FILE;
+
+/// Configuration for precomputing the vtables of a set of headers
+#[derive(Debug, Clone, Default)]
+pub struct Builder {
+    headers: Vec<PathBuf>,
+    flags: Vec<String>,
+    classes: Vec<String>,
+    #[cfg(feature = "bindgen")]
+    bindings: bool,
+}
+
+impl Builder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a header (or object file/PDB), relative to the crate root. The path written in
+    /// `#[inherit_from_impl]` stays relative to `src`.
+    pub fn header<P: AsRef<Path>>(&mut self, header: P) -> &mut Self {
+        self.headers.push(header.as_ref().to_owned());
+        self
+    }
+
+    /// Add a flag to pass to the C++ compiler, e.g. an include directory or define
+    pub fn flag(&mut self, flag: &str) -> &mut Self {
+        self.flags.push(flag.to_owned());
+        self
+    }
+
+    /// Only keep the vtables of the given classes. By default every class is kept.
+    pub fn class(&mut self, class: &str) -> &mut Self {
+        self.classes.push(class.to_owned());
+        self
+    }
+
+    /// Also generate bindings for the headers' types with bindgen, using the same flags, into
+    /// `cpp-inherit-bindings.rs` in `OUT_DIR`:
+    ///
+    /// ```ignore
+    /// include!(concat!(env!("OUT_DIR"), "/cpp-inherit-bindings.rs"));
+    /// ```
+    ///
+    /// When classes are given, only they (and the types they use) are generated.
+    #[cfg(feature = "bindgen")]
+    pub fn bindings(&mut self, bindings: bool) -> &mut Self {
+        self.bindings = bindings;
+        self
+    }
+
+    /// Compile the headers and write their vtables to the metadata file in `OUT_DIR`
+    pub fn generate(&self) {
+        let out_dir = PathBuf::from(env::var_os("OUT_DIR").expect("OUT_DIR is not set"));
+        let target = env::var("TARGET").expect("TARGET is not set");
+        let manifest_dir = PathBuf::from(
+            env::var_os("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR is not set"),
+        );
+
+        for instruction in self.generate_in(&out_dir, &target, &manifest_dir) {
+            println!("cargo:{}", instruction);
+        }
+    }
+
+    /// [`Builder::generate`], returning the instructions for cargo rather than printing them
+    fn generate_in(&self, out_dir: &Path, target: &str, manifest_dir: &Path) -> Vec<String> {
+        // The macros can't otherwise tell which target is being built
+        let mut instructions = vec![
+            format!("rustc-env=CPP_INHERIT_TARGET={}", target),
+            "rerun-if-env-changed=CXX".to_owned(),
+            format!("rerun-if-env-changed=CXX_{}", target.replace('-', "_")),
+        ];
+
+        let mut found_classes = Vec::new();
+        let headers = self
+            .headers
+            .iter()
+            .enumerate()
+            .map(|(index, header)| {
+                let header = manifest_dir.join(header);
+                let header = header
+                    .canonicalize()
+                    .unwrap_or_else(|error| panic!("{}: {}", header.display(), error));
+                instructions.push(format!("rerun-if-changed={}", header.display()));
+
+                // Have the compiler list the headers which are included, so changes to those
+                // are tracked too
+                let out_path = out_dir.join(format!("cpp-inherit-{}.o", index));
+                let dep_path = out_path.with_extension("d");
+                let mut flags = self.flags.clone();
+                flags.extend(vec![
+                    "-MD".into(),
+                    "-MF".into(),
+                    dep_path.display().to_string(),
+                ]);
+
+                let mut info = cpp_inherit_core::read_vtable_info_with_flags(
+                    &header, &out_path, target, &flags,
+                );
+                // Files written to `OUT_DIR` are newer than the last run, so tracking them would
+                // rerun the build script every time
+                if let Ok(deps) = fs::read_to_string(&dep_path) {
+                    for dep in parse_depfile(&deps) {
+                        if !Path::new(&dep).starts_with(out_dir) {
+                            instructions.push(format!("rerun-if-changed={}", dep));
+                        }
+                    }
+                }
+
+                if !self.classes.is_empty() {
+                    info.classes.retain(|class, _| self.classes.contains(class));
+                    info.constructors
+                        .retain(|class, _| self.classes.contains(class));
+                }
+                found_classes.extend(info.classes.keys().cloned());
+
+                HeaderMetadata {
+                    header,
+                    target: target.to_owned(),
+                    info,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        for class in &self.classes {
+            if !found_classes.contains(class) {
+                panic!("Class `{}` was not found in any header", class);
+            }
+        }
+
+        #[cfg(feature = "bindgen")]
+        if self.bindings {
+            let paths = headers
+                .iter()
+                .map(|header| header.header.clone())
+                .collect::<Vec<_>>();
+            bindings::generate(&paths, &self.flags, &self.classes, &headers, out_dir);
+        }
+
+        metadata::write(out_dir, &headers);
+        instructions
+    }
+}
+
+/// The prerequisites listed in a Makefile-style dependency file written by `-MD`
+fn parse_depfile(deps: &str) -> Vec<String> {
+    let deps = deps.replace("\\\n", " ");
+    let prerequisites = match deps.split_once(": ") {
+        Some((_, prerequisites)) => prerequisites,
+        None => return Vec::new(),
+    };
+
+    // Spaces in paths are escaped with a backslash
+    let mut paths = Vec::new();
+    let mut path = String::new();
+    for word in prerequisites.split_whitespace() {
+        match word.strip_suffix('\\') {
+            Some(word) => {
+                path.push_str(word);
+                path.push(' ');
+            }
+            None => {
+                path.push_str(word);
+                paths.push(std::mem::take(&mut path));
+            }
+        }
+    }
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::SystemTime;
+
+    #[test]
+    fn unchanged_inputs_are_clean() {
+        let dir = env::temp_dir().join(format!("cpp-inherit-build-{}", std::process::id()));
+        let out_dir = dir.join("out");
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::create_dir_all(&out_dir).unwrap();
+        fs::write(dir.join("src/included.hpp"), "struct member { int x; };\n").unwrap();
+        fs::write(
+            dir.join("src/test.hpp"),
+            "#include \"included.hpp\"\n\
+             struct base { member m; virtual int x() = 0; virtual ~base(); };\n",
+        )
+        .unwrap();
+
+        let mut builder = Builder::new();
+        builder.header("src/test.hpp");
+        let target = &cpp_inherit_core::target_triple();
+        let rerun_paths = |instructions: Vec<String>| {
+            instructions
+                .iter()
+                .filter_map(|instruction| instruction.strip_prefix("rerun-if-changed="))
+                .map(PathBuf::from)
+                .collect::<Vec<_>>()
+        };
+
+        let first = rerun_paths(builder.generate_in(&out_dir, target, &dir));
+        let first_run = SystemTime::now();
+        let second = rerun_paths(builder.generate_in(&out_dir, target, &dir));
+        assert_eq!(first, second);
+        assert!(second.contains(&dir.join("src/included.hpp").canonicalize().unwrap()));
+
+        // Cargo reruns the build script when a file it was told about is newer than its last run
+        for path in &second {
+            assert!(!path.starts_with(&out_dir), "{}", path.display());
+            let modified = fs::metadata(path).unwrap().modified().unwrap();
+            assert!(modified <= first_run, "{}", path.display());
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,10 @@
+use std::env;
+
+fn main() {
+    // Proc macros are built for the host, so this is the target to assume when the crate using
+    // the macros doesn't say otherwise
+    println!(
+        "cargo:rustc-env=CPP_INHERIT_HOST={}",
+        env::var("TARGET").unwrap()
+    );
+}
@@ -0,0 +1,409 @@
+//! MSVC ABI vtables from the CodeView type info in a PDB
+
+use pdb::FallibleIterator;
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::Path,
+};
+
+use super::{CppAbi, Qualifiers, VTableElement, VTableInfo, Virtuality};
+
+pub fn get_vtables_from_pdb(path: &Path) -> VTableInfo {
+    let file = fs::File::open(path).unwrap();
+    let mut pdb = pdb::PDB::open(file).unwrap();
+
+    dump_pdb(&mut pdb).unwrap()
+}
+
+/// The symbols which implement methods
+struct Symbols {
+    /// The decorated names of the public symbols, with their addresses
+    publics: Vec<(pdb::PdbInternalSectionOffset, String)>,
+    /// The addresses of functions, by their type and undecorated name (e.g. `ns::Class::method`)
+    procedures: HashMap<(pdb::TypeIndex, String), Vec<pdb::PdbInternalSectionOffset>>,
+}
+
+/// Find the decorated name of a method's implementation among the public symbols.
+///
+/// Type records only hold undecorated names, so the method's type and name are looked up among
+/// the procedures in the modules' symbols, whose address is that of its public symbol. Without a
+/// procedure this falls back on the `?name@Class@ns@@` prefix MSVC mangles member functions with,
+/// if only one symbol has it. Virtual destructors occupy a single slot which points at the vector
+/// (`??_E`) or scalar (`??_G`) deleting destructor.
+fn find_symbol(
+    symbols: &Symbols,
+    class: &str,
+    method: &str,
+    method_type: pdb::TypeIndex,
+) -> Option<String> {
+    // `ns::Class` is mangled innermost-first as `Class@ns@`
+    let scope = class
+        .rsplit("::")
+        .map(|part| format!("{}@", part))
+        .collect::<String>();
+    fn with_prefix<'a>(
+        symbols: &'a Symbols,
+        prefix: &'a str,
+    ) -> impl Iterator<Item = &'a (pdb::PdbInternalSectionOffset, String)> {
+        symbols
+            .publics
+            .iter()
+            .filter(move |(_, symbol)| symbol.starts_with(prefix))
+    }
+
+    if method.starts_with('~') {
+        return [format!("??_E{}@", scope), format!("??_G{}@", scope)]
+            .iter()
+            .find_map(|prefix| with_prefix(symbols, prefix).next())
+            .map(|(_, symbol)| symbol.clone());
+    }
+
+    let prefix = format!("?{}@{}@", method, scope);
+    let addresses = symbols
+        .procedures
+        .get(&(method_type, format!("{}::{}", class, method)));
+    // Identical functions can be folded into one, so match on the prefix too
+    if let Some(addresses) = addresses {
+        let symbol = with_prefix(symbols, &prefix).find(|(address, _)| addresses.contains(address));
+        if let Some((_, symbol)) = symbol {
+            return Some(symbol.clone());
+        }
+    }
+
+    let mut candidates = with_prefix(symbols, &prefix);
+    match (candidates.next(), candidates.next()) {
+        (Some((_, symbol)), None) => Some(symbol.clone()),
+        _ => None,
+    }
+}
+
+/// What tells overloads of a method apart, to find the slot an override replaces
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Overload {
+    arguments: pdb::TypeIndex,
+    qualifiers: Qualifiers,
+}
+
+/// The overload a method's `LF_MFUNCTION` type describes, with the cv qualifiers of its `this`
+fn overload(finder: &pdb::TypeFinder, method_type: pdb::TypeIndex) -> pdb::Result<Overload> {
+    let function = match finder.find(method_type)?.parse()? {
+        pdb::TypeData::MemberFunction(function) => function,
+        _ => {
+            return Err(pdb::Error::UnimplementedFeature(
+                "non-member function method",
+            ))
+        }
+    };
+
+    let mut qualifiers = Qualifiers::default();
+    if let Some(this) = function.this_pointer_type {
+        if let pdb::TypeData::Pointer(pointer) = finder.find(this)?.parse()? {
+            if let pdb::TypeData::Modifier(modifier) =
+                finder.find(pointer.underlying_type)?.parse()?
+            {
+                qualifiers.is_const = modifier.constant;
+                qualifiers.is_volatile = modifier.volatile;
+            }
+        }
+    }
+
+    Ok(Overload {
+        arguments: function.argument_list,
+        qualifiers,
+    })
+}
+
+struct MethodSlot<'a> {
+    class: &'a str,
+    name: String,
+    attributes: pdb::FieldAttributes,
+    method_type: pdb::TypeIndex,
+    vtable_offset: Option<u32>,
+}
+
+fn add_method(
+    vtable: &mut Vec<(VTableElement, Overload)>,
+    slot: MethodSlot,
+    finder: &pdb::TypeFinder,
+    address_size: u8,
+    symbols: &Symbols,
+) -> pdb::Result<()> {
+    let MethodSlot {
+        class,
+        name,
+        attributes,
+        method_type,
+        vtable_offset,
+    } = slot;
+
+    if !(attributes.is_virtual() || attributes.is_pure_virtual() || attributes.is_intro_virtual()) {
+        return Ok(());
+    }
+    let overload = overload(finder, method_type)?;
+
+    // Only the method introducing a slot records its offset, overrides reuse the slot of the
+    // base's method with the same name and overload (or its destructor)
+    let pos = match vtable_offset {
+        Some(offset) => u64::from(offset / u32::from(address_size)),
+        None => {
+            let base = vtable.iter().find(|(entry, base_overload)| {
+                if name.starts_with('~') {
+                    entry.name.starts_with('~')
+                } else {
+                    entry.name == name && *base_overload == overload
+                }
+            });
+            match base {
+                Some((entry, _)) => entry.pos,
+                None => return Ok(()),
+            }
+        }
+    };
+
+    // Pure virtual methods have no symbol, leaving `default` empty requires an override
+    let default = find_symbol(symbols, class, &name, method_type).unwrap_or_default();
+    let virtuality = if attributes.is_pure_virtual() {
+        Virtuality::PureVirtual
+    } else {
+        Virtuality::Virtual
+    };
+
+    let element = VTableElement {
+        default,
+        name,
+        pos,
+        virtuality,
+        // Only cv qualifiers are recorded on `this`, and the types aren't read from PDBs yet
+        qualifiers: overload.qualifiers,
+        return_type: None,
+        params: Vec::new(),
+    };
+
+    match vtable.iter_mut().find(|(entry, _)| entry.pos == pos) {
+        Some(entry) => *entry = (element, overload),
+        None => vtable.push((element, overload)),
+    }
+    Ok(())
+}
+
+/// Read the public symbols, and the procedures in each module
+fn read_symbols<'s, S: pdb::Source<'s> + 's>(pdb: &mut pdb::PDB<'s, S>) -> pdb::Result<Symbols> {
+    let mut publics = Vec::new();
+    let global_symbols = pdb.global_symbols()?;
+    let mut iter = global_symbols.iter();
+    while let Some(symbol) = iter.next()? {
+        if let Ok(pdb::SymbolData::Public(public)) = symbol.parse() {
+            publics.push((public.offset, public.name.to_string().into_owned()));
+        }
+    }
+
+    let mut procedures: HashMap<_, Vec<_>> = HashMap::new();
+    let debug_information = pdb.debug_information()?;
+    let mut modules = debug_information.modules()?;
+    while let Some(module) = modules.next()? {
+        let info = match pdb.module_info(&module)? {
+            Some(info) => info,
+            None => continue,
+        };
+        let mut iter = info.symbols()?;
+        while let Some(symbol) = iter.next()? {
+            if let Ok(pdb::SymbolData::Procedure(procedure)) = symbol.parse() {
+                let name = procedure.name.to_string().into_owned();
+                procedures
+                    .entry((procedure.type_index, name))
+                    .or_default()
+                    .push(procedure.offset);
+            }
+        }
+    }
+
+    Ok(Symbols {
+        publics,
+        procedures,
+    })
+}
+
+fn dump_pdb<'s, S: pdb::Source<'s> + 's>(pdb: &mut pdb::PDB<'s, S>) -> pdb::Result<VTableInfo> {
+    let architecture = match pdb.debug_information()?.machine_type()? {
+        pdb::MachineType::X86 => object::Architecture::I386,
+        pdb::MachineType::Amd64 => object::Architecture::X86_64,
+        pdb::MachineType::Arm64 => object::Architecture::Aarch64,
+        pdb::MachineType::Arm | pdb::MachineType::ArmNT | pdb::MachineType::Thumb => {
+            object::Architecture::Arm
+        }
+        _ => object::Architecture::Unknown,
+    };
+    let address_size = match architecture {
+        object::Architecture::I386 | object::Architecture::Arm => 4,
+        _ => 8,
+    };
+
+    let symbols = read_symbols(pdb)?;
+
+    let type_information = pdb.type_information()?;
+    let mut finder = type_information.finder();
+    let mut classes: HashMap<String, Vec<(VTableElement, Overload)>> = HashMap::new();
+
+    let mut iter = type_information.iter();
+    while let Some(ty) = iter.next()? {
+        finder.update(&iter);
+
+        let class = match ty.parse() {
+            Ok(pdb::TypeData::Class(class)) if !class.properties.forward_reference() => class,
+            _ => continue,
+        };
+        let class_name = class.name.to_string().into_owned();
+
+        let mut vtable: Vec<(VTableElement, Overload)> = Vec::new();
+        let mut field_list = class.fields;
+        while let Some(index) = field_list {
+            let list = match finder.find(index)?.parse()? {
+                pdb::TypeData::FieldList(list) => list,
+                _ => break,
+            };
+
+            for field in list.fields {
+                match field {
+                    // The first base with a vtable is the primary base, whose slots come first
+                    pdb::TypeData::BaseClass(base) if vtable.is_empty() => {
+                        if let pdb::TypeData::Class(base) = finder.find(base.base_class)?.parse()? {
+                            if let Some(base_vtable) = classes.get(&*base.name.to_string()) {
+                                vtable = base_vtable.clone();
+                            }
+                        }
+                    }
+                    pdb::TypeData::Method(method) => add_method(
+                        &mut vtable,
+                        MethodSlot {
+                            class: &class_name,
+                            name: method.name.to_string().into_owned(),
+                            attributes: method.attributes,
+                            method_type: method.method_type,
+                            vtable_offset: method.vtable_offset,
+                        },
+                        &finder,
+                        address_size,
+                        &symbols,
+                    )?,
+                    pdb::TypeData::OverloadedMethod(overloads) => {
+                        let methods = match finder.find(overloads.method_list)?.parse()? {
+                            pdb::TypeData::MethodList(list) => list.methods,
+                            _ => continue,
+                        };
+                        for method in methods {
+                            add_method(
+                                &mut vtable,
+                                MethodSlot {
+                                    class: &class_name,
+                                    name: overloads.name.to_string().into_owned(),
+                                    attributes: method.attributes,
+                                    method_type: method.method_type,
+                                    vtable_offset: method.vtable_offset,
+                                },
+                                &finder,
+                                address_size,
+                                &symbols,
+                            )?;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            field_list = list.continuation;
+        }
+
+        vtable.sort_by_key(|(entry, _)| entry.pos);
+        classes.insert(class_name, vtable);
+    }
+
+    let classes = classes
+        .into_iter()
+        .map(|(class, vtable)| (class, vtable.into_iter().map(|(entry, _)| entry).collect()))
+        .collect();
+
+    Ok(VTableInfo {
+        abi: CppAbi::Msvc,
+        architecture,
+        address_size,
+        classes,
+        // Constructors aren't read from PDBs yet
+        constructors: HashMap::new(),
+        non_trivial_classes: HashSet::new(),
+        bases: HashMap::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> std::path::PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name)
+    }
+
+    fn layout<'a>(info: &'a VTableInfo, class: &str) -> Vec<(u64, &'a str, &'a str)> {
+        info.get(class)
+            .unwrap()
+            .iter()
+            .map(|entry| (entry.pos, entry.name.as_str(), entry.default.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn overloads() {
+        let info = get_vtables_from_pdb(&fixture("msvc.pdb"));
+        assert_eq!(info.abi, CppAbi::Msvc);
+        assert_eq!(info.architecture, object::Architecture::I386);
+        assert_eq!(info.address_size, 4);
+
+        // `x()` and `other()` have the same type, and `z` is only found by its prefix
+        assert_eq!(
+            layout(&info, "base"),
+            [
+                (0, "~base", "??_Ebase@@UAEPAXI@Z"),
+                (1, "x", "?x@base@@UAEHXZ"),
+                (2, "x", "?x@base@@UAEHH@Z"),
+                (3, "x", "?x@base@@UBEHXZ"),
+                (4, "pure", ""),
+                (5, "other", "?other@base@@UAEHXZ"),
+                (6, "z", "?z@base@@UAEHXZ"),
+            ]
+        );
+        let base = info.get("base").unwrap();
+        let qualifiers = base
+            .iter()
+            .map(|entry| entry.qualifiers.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(qualifiers, ["", "", "", "const", "", "", ""]);
+        let must_override = base
+            .iter()
+            .filter(|entry| entry.must_override())
+            .map(|entry| entry.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(must_override, ["pure"]);
+    }
+
+    #[test]
+    fn overrides() {
+        let info = get_vtables_from_pdb(&fixture("msvc.pdb"));
+
+        // Overrides replace the slot of the overload they match, and the destructor's
+        assert_eq!(
+            layout(&info, "derived"),
+            [
+                (0, "~derived", "??_Gderived@@UAEPAXI@Z"),
+                (1, "x", "?x@base@@UAEHXZ"),
+                (2, "x", "?x@derived@@UAEHH@Z"),
+                (3, "x", "?x@base@@UBEHXZ"),
+                (4, "pure", "?pure@derived@@UAEHXZ"),
+                (5, "other", "?other@base@@UAEHXZ"),
+                (6, "z", "?z@base@@UAEHXZ"),
+                (7, "y", "?y@derived@@UAEHXZ"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,1922 @@
+//! Itanium ABI vtables from the DWARF debug info of an object file
+
+use object::{Object, ObjectSection};
+use std::{
+    borrow::{Borrow, Cow},
+    collections::{HashMap, HashSet},
+    fmt, fs,
+    path::Path,
+    str::FromStr,
+};
+use typed_arena::Arena;
+
+use super::{
+    target_arch, target_endian, target_pointer_width, BaseClass, Constructor, CppAbi, CppType,
+    VTableInfo,
+};
+
+type RelocationMap = HashMap<usize, Relocation>;
+
+/// A relocation resolved against its symbol, keyed by the offset it applies to
+#[derive(Debug, Clone)]
+enum Relocation {
+    /// Replaces the value in the section (relocations with an explicit addend)
+    Set(u64),
+    /// Adds to the value in the section (relocations with an implicit addend, and RISC-V's
+    /// ADD/SUB pairs which encode the difference between two labels)
+    Add(u64),
+    /// Reading the value is an error, as it can't be relocated
+    Unsupported(String),
+}
+
+impl Relocation {
+    /// Apply another relocation to the same offset
+    fn combine(self, other: Relocation) -> Relocation {
+        match (self, other) {
+            (Relocation::Unsupported(message), _) | (_, Relocation::Unsupported(message)) => {
+                Relocation::Unsupported(message)
+            }
+            (Relocation::Set(a), Relocation::Add(b)) | (Relocation::Add(a), Relocation::Set(b)) => {
+                Relocation::Set(a.wrapping_add(b))
+            }
+            (Relocation::Add(a), Relocation::Add(b)) => Relocation::Add(a.wrapping_add(b)),
+            (Relocation::Set(_), Relocation::Set(b)) => Relocation::Set(b),
+        }
+    }
+}
+
+/// Ensure the object was compiled for the target, otherwise the vtable layout can't be trusted
+fn check_target(object: &object::File, target: &str) {
+    let target_arch = target_arch(target);
+    let arch = match object.architecture() {
+        object::Architecture::Aarch64 => "aarch64",
+        object::Architecture::Arm => "arm",
+        object::Architecture::I386 => "x86",
+        // Covers every MIPS variant, the pointer width tells them apart
+        object::Architecture::Mips if target_arch.starts_with("mips") => target_arch,
+        object::Architecture::Mips => "mips",
+        object::Architecture::Wasm32 => "wasm32",
+        object::Architecture::X86_64 => "x86_64",
+        object::Architecture::Unknown => target_arch,
+    };
+    let pointer_width = if object.is_64() { 64 } else { 32 };
+    let endian = if object.is_little_endian() {
+        "little"
+    } else {
+        "big"
+    };
+
+    if arch != target_arch
+        || pointer_width != target_pointer_width(target)
+        || endian != target_endian(target)
+    {
+        panic!(
+            "Header was compiled for {} ({}-bit, {} endian) but the target is {} ({}-bit, {} endian)",
+            arch,
+            pointer_width,
+            endian,
+            target,
+            target_pointer_width(target),
+            target_endian(target)
+        );
+    }
+}
+
+pub fn get_vtables_from_file(path: &Path, target: &str) -> VTableInfo {
+    read_file(path, target).0
+}
+
+/// Like [`get_vtables_from_file`], for an object compiled from `header` by a compiler which might
+/// not mark pure virtual methods in the debug info, as g++ doesn't. Those are found from the
+/// vtables of classes deriving from each class instead (see [`pure_virtual_probe`]), which
+/// `compile` is called to compile to an object, returning its path.
+pub fn get_vtables_from_header(
+    path: &Path,
+    header: &Path,
+    target: &str,
+    mut compile: impl FnMut(&str) -> Option<std::path::PathBuf>,
+) -> VTableInfo {
+    let (mut info, qualified_names) = read_file(path, target);
+
+    let pure_virtual = |entry: &VTableElement| entry.virtuality == Virtuality::PureVirtual;
+    if info.classes.values().flatten().any(pure_virtual) {
+        return info;
+    }
+
+    let mut classes = info
+        .classes
+        .iter()
+        .filter(|(_, vtable)| !vtable.is_empty())
+        .filter_map(|(class, _)| Some((class.clone(), qualified_names.get(class)?.as_str())))
+        .collect::<Vec<_>>();
+    classes.sort();
+    let (names, qualified): (Vec<_>, Vec<_>) = classes.into_iter().unzip();
+    let names = names.iter().map(String::as_str).collect::<Vec<_>>();
+
+    // Some classes can't be derived from (e.g. `final` ones), which fails the whole probe, so
+    // then probe them one at a time
+    match compile(&pure_virtual_probe(header, &qualified)) {
+        Some(probe) => mark_pure_virtuals(&mut info, &probe, &names),
+        None => {
+            for (name, qualified) in names.iter().zip(&qualified) {
+                if let Some(probe) = compile(&pure_virtual_probe(header, &[qualified])) {
+                    mark_pure_virtuals(&mut info, &probe, &[name]);
+                }
+            }
+        }
+    }
+
+    info
+}
+
+/// A translation unit deriving a class from each of `classes` (qualified by their namespaces)
+/// in `header`, with an out-of-line virtual method so its vtable is emitted. The Itanium ABI
+/// fills the slots of pure virtual methods in those vtables with `__cxa_pure_virtual`.
+pub fn pure_virtual_probe(header: &Path, classes: &[&str]) -> String {
+    let header = header
+        .to_string_lossy()
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"");
+    let mut source = format!("#include \"{}\"\n", header);
+    for (i, class) in classes.iter().enumerate() {
+        source += &format!(
+            "struct cpp_inherit_probe_{i} : {class} {{ virtual void cpp_inherit_probe(); }};\n\
+             void cpp_inherit_probe_{i}::cpp_inherit_probe() {{}}\n",
+            i = i,
+            class = class
+        );
+    }
+    source
+}
+
+/// Mark the methods of `classes` whose slots are `__cxa_pure_virtual` in the vtables of the
+/// object compiled from their [`pure_virtual_probe`] as pure virtual
+pub fn mark_pure_virtuals(info: &mut VTableInfo, probe: &Path, classes: &[&str]) {
+    let file = match fs::File::open(probe) {
+        Ok(file) => file,
+        Err(_) => return,
+    };
+    let mmap = unsafe { memmap::Mmap::map(&file).unwrap() };
+    let object = match object::File::parse(&mmap) {
+        Ok(object) => object,
+        Err(_) => return,
+    };
+    let address_size = u64::from(info.address_size);
+
+    let target_name = |relocation: &object::Relocation| match relocation.target() {
+        object::RelocationTarget::Symbol(index) => object
+            .symbol_by_index(index)
+            .ok()
+            .and_then(|symbol| symbol.name()),
+        object::RelocationTarget::Section(_) => None,
+    };
+
+    for (i, class) in classes.iter().enumerate() {
+        let probe = format!("cpp_inherit_probe_{}", i);
+        let probe = format!("{}{}", probe.len(), probe);
+        let vtable_name = format!("_ZTV{}", probe);
+        let typeinfo_name = format!("_ZTI{}", probe);
+        let symbol = object
+            .symbols()
+            .map(|(_, symbol)| symbol)
+            .find(|symbol| symbol.name() == Some(&vtable_name));
+        let (symbol, section) = match symbol
+            .and_then(|symbol| Some((symbol.clone(), symbol.section_index()?)))
+            .and_then(|(symbol, index)| Some((symbol, object.section_by_index(index).ok()?)))
+        {
+            Some(found) => found,
+            None => continue,
+        };
+        let range = symbol.address()..symbol.address() + symbol.size();
+        let relocations = section
+            .relocations()
+            .filter(|(offset, _)| range.contains(offset))
+            .collect::<Vec<_>>();
+
+        // The slots start after the typeinfo, which is preceded by the offset to top, and for
+        // classes with virtual bases, their offsets
+        let typeinfo = relocations
+            .iter()
+            .find(|(_, relocation)| target_name(relocation) == Some(&typeinfo_name))
+            .map_or(range.start + address_size, |(offset, _)| *offset);
+        let pure = relocations
+            .iter()
+            .filter(|(offset, _)| *offset > typeinfo)
+            .filter(|(_, relocation)| target_name(relocation) == Some("__cxa_pure_virtual"))
+            .map(|(offset, _)| (offset - typeinfo) / address_size - 1)
+            .collect::<HashSet<_>>();
+
+        if let Some(vtable) = info.classes.get_mut(*class) {
+            for entry in vtable.iter_mut().filter(|entry| pure.contains(&entry.pos)) {
+                entry.virtuality = Virtuality::PureVirtual;
+            }
+        }
+    }
+}
+
+fn read_file(path: &Path, target: &str) -> (VTableInfo, HashMap<String, String>) {
+    let file = fs::File::open(path).unwrap();
+    let mmap = unsafe { memmap::Mmap::map(&file).unwrap() };
+    let object = object::File::parse(&mmap).unwrap();
+    check_target(&object, target);
+    check_arm_eabi(&object, &mmap);
+    let machine = elf_machine(&mmap);
+    let classes = dump_file(path, &object, machine).unwrap_or_else(|error| {
+        panic!(
+            "Failed to read the debug info in {}: {}",
+            path.display(),
+            error
+        )
+    });
+
+    let info = VTableInfo {
+        abi: CppAbi::Itanium,
+        architecture: object.architecture(),
+        address_size: if object.is_64() { 8 } else { 4 },
+        non_trivial_classes: non_trivial_classes(&classes.triviality),
+        classes: classes.vtables,
+        constructors: classes.constructors,
+        bases: classes.bases,
+    };
+    (info, classes.qualified_names)
+}
+
+#[derive(Debug, Clone)]
+struct Relocate<'a, R: gimli::Reader<Offset = usize>> {
+    relocations: &'a RelocationMap,
+    section: R,
+    reader: R,
+}
+
+impl<'a, R: gimli::Reader<Offset = usize>> Relocate<'a, R> {
+    fn relocate(&self, offset: usize, value: u64) -> u64 {
+        match self.relocations.get(&offset) {
+            Some(Relocation::Set(relocated)) => *relocated,
+            Some(Relocation::Add(addend)) => value.wrapping_add(*addend),
+            Some(Relocation::Unsupported(message)) => panic!("{}", message),
+            None => value,
+        }
+    }
+}
+
+impl<'a, R: gimli::Reader<Offset = usize>> gimli::Reader for Relocate<'a, R> {
+    type Endian = R::Endian;
+    type Offset = R::Offset;
+
+    fn read_address(&mut self, address_size: u8) -> gimli::Result<u64> {
+        let offset = self.reader.offset_from(&self.section);
+        let value = self.reader.read_address(address_size)?;
+        Ok(self.relocate(offset, value))
+    }
+
+    fn read_length(&mut self, format: gimli::Format) -> gimli::Result<usize> {
+        let offset = self.reader.offset_from(&self.section);
+        let value = self.reader.read_length(format)?;
+        <usize as gimli::ReaderOffset>::from_u64(self.relocate(offset, value as u64))
+    }
+
+    fn read_offset(&mut self, format: gimli::Format) -> gimli::Result<usize> {
+        let offset = self.reader.offset_from(&self.section);
+        let value = self.reader.read_offset(format)?;
+        <usize as gimli::ReaderOffset>::from_u64(self.relocate(offset, value as u64))
+    }
+
+    fn read_sized_offset(&mut self, size: u8) -> gimli::Result<usize> {
+        let offset = self.reader.offset_from(&self.section);
+        let value = self.reader.read_sized_offset(size)?;
+        <usize as gimli::ReaderOffset>::from_u64(self.relocate(offset, value as u64))
+    }
+
+    #[inline]
+    fn split(&mut self, len: Self::Offset) -> gimli::Result<Self> {
+        let mut other = self.clone();
+        other.reader.truncate(len)?;
+        self.reader.skip(len)?;
+        Ok(other)
+    }
+
+    // All remaining methods simply delegate to `self.reader`.
+
+    #[inline]
+    fn endian(&self) -> Self::Endian {
+        self.reader.endian()
+    }
+
+    #[inline]
+    fn len(&self) -> Self::Offset {
+        self.reader.len()
+    }
+
+    #[inline]
+    fn empty(&mut self) {
+        self.reader.empty()
+    }
+
+    #[inline]
+    fn truncate(&mut self, len: Self::Offset) -> gimli::Result<()> {
+        self.reader.truncate(len)
+    }
+
+    #[inline]
+    fn offset_from(&self, base: &Self) -> Self::Offset {
+        self.reader.offset_from(&base.reader)
+    }
+
+    #[inline]
+    fn offset_id(&self) -> gimli::ReaderOffsetId {
+        self.reader.offset_id()
+    }
+
+    #[inline]
+    fn lookup_offset_id(&self, id: gimli::ReaderOffsetId) -> Option<Self::Offset> {
+        self.reader.lookup_offset_id(id)
+    }
+
+    #[inline]
+    fn find(&self, byte: u8) -> gimli::Result<Self::Offset> {
+        self.reader.find(byte)
+    }
+
+    #[inline]
+    fn skip(&mut self, len: Self::Offset) -> gimli::Result<()> {
+        self.reader.skip(len)
+    }
+
+    #[inline]
+    fn to_slice(&self) -> gimli::Result<Cow<'_, [u8]>> {
+        self.reader.to_slice()
+    }
+
+    #[inline]
+    fn to_string(&self) -> gimli::Result<Cow<'_, str>> {
+        self.reader.to_string()
+    }
+
+    #[inline]
+    fn to_string_lossy(&self) -> gimli::Result<Cow<'_, str>> {
+        self.reader.to_string_lossy()
+    }
+
+    #[inline]
+    fn read_slice(&mut self, buf: &mut [u8]) -> gimli::Result<()> {
+        self.reader.read_slice(buf)
+    }
+}
+
+/// The ELF `e_machine` of the file, if it is an ELF file
+fn elf_machine(data: &[u8]) -> Option<u16> {
+    if data.get(..4)? != b"\x7fELF" {
+        return None;
+    }
+    let machine = [*data.get(18)?, *data.get(19)?];
+    match data.get(5)? {
+        1 => Some(u16::from_le_bytes(machine)),
+        _ => Some(u16::from_be_bytes(machine)),
+    }
+}
+
+/// The flags in a 32-bit ELF header
+fn elf_flags32(data: &[u8]) -> Option<u32> {
+    if data.get(..5)? != b"\x7fELF\x01" {
+        return None;
+    }
+    let flags = [
+        *data.get(36)?,
+        *data.get(37)?,
+        *data.get(38)?,
+        *data.get(39)?,
+    ];
+    match data.get(5)? {
+        1 => Some(u32::from_le_bytes(flags)),
+        _ => Some(u32::from_be_bytes(flags)),
+    }
+}
+
+/// Only the ARM C++ ABI (`C1`/`D1` returning `this` and the like) is implemented for ARM, which
+/// objects from before the EABI (version 0 in the header's flags) didn't follow
+fn check_arm_eabi(object: &object::File, data: &[u8]) {
+    const EF_ARM_EABIMASK: u32 = 0xff00_0000;
+    if object.architecture() == object::Architecture::Arm
+        && elf_flags32(data).is_some_and(|flags| flags & EF_ARM_EABIMASK == 0)
+    {
+        panic!("ARM objects must use the EABI, the old ABI's C++ ABI isn't supported");
+    }
+}
+
+/// What a relocation computes from its symbol's value and addend
+enum Operation {
+    /// `S + A`
+    Absolute,
+    /// `S + A - Section`, used by COFF for offsets into other debug sections
+    SectionOffset,
+    /// Adds `S + A` to the place
+    Add,
+    /// Subtracts `S + A` from the place
+    Sub,
+}
+
+/// Classify a relocation, including ones `object` doesn't for architectures such as PowerPC,
+/// MIPS and RISC-V
+fn operation(kind: object::RelocationKind, machine: Option<u16>) -> Option<Operation> {
+    use object::elf::*;
+
+    match (kind, machine) {
+        (object::RelocationKind::Absolute, _) => Some(Operation::Absolute),
+        (object::RelocationKind::SectionOffset, _) => Some(Operation::SectionOffset),
+        (object::RelocationKind::Elf(r_type), Some(machine)) => match (machine, r_type) {
+            (EM_PPC, R_PPC_ADDR32)
+            | (EM_PPC64, R_PPC64_ADDR32)
+            | (EM_PPC64, R_PPC64_ADDR64)
+            | (EM_MIPS, R_MIPS_32)
+            | (EM_MIPS, R_MIPS_64)
+            | (EM_S390, R_390_32)
+            | (EM_S390, R_390_64)
+            | (EM_SPARCV9, R_SPARC_32)
+            | (EM_SPARCV9, R_SPARC_UA32)
+            | (EM_SPARCV9, R_SPARC_UA64)
+            | (EM_RISCV, R_RISCV_32)
+            | (EM_RISCV, R_RISCV_64)
+            | (EM_RISCV, R_RISCV_SET8)
+            | (EM_RISCV, R_RISCV_SET16)
+            | (EM_RISCV, R_RISCV_SET32) => Some(Operation::Absolute),
+            (EM_RISCV, R_RISCV_ADD8)
+            | (EM_RISCV, R_RISCV_ADD16)
+            | (EM_RISCV, R_RISCV_ADD32)
+            | (EM_RISCV, R_RISCV_ADD64) => Some(Operation::Add),
+            (EM_RISCV, R_RISCV_SUB8)
+            | (EM_RISCV, R_RISCV_SUB16)
+            | (EM_RISCV, R_RISCV_SUB32)
+            | (EM_RISCV, R_RISCV_SUB64) => Some(Operation::Sub),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Resolve a relocation's symbol, giving `S` and the address of the section it's in
+fn symbol_value(
+    file: &object::File,
+    target: object::RelocationTarget,
+) -> object::read::Result<(u64, u64)> {
+    let section_index = match target {
+        object::RelocationTarget::Symbol(symbol_idx) => {
+            let symbol = file.symbol_by_index(symbol_idx)?;
+            let section = match symbol.section_index() {
+                Some(index) => file.section_by_index(index)?.address(),
+                None => 0,
+            };
+            return Ok((symbol.address(), section));
+        }
+        object::RelocationTarget::Section(section_idx) => section_idx,
+    };
+    let address = file.section_by_index(section_index)?.address();
+    Ok((address, address))
+}
+
+/// Resolve the relocations for a section which is being placed `base` bytes into the data the
+/// reader sees.
+///
+/// Relocations which can't be resolved are only reported if the DWARF reader reads the value
+/// they apply to, since sections often contain relocations for data we never look at.
+fn add_relocations(
+    relocations: &mut RelocationMap,
+    file: &object::File,
+    machine: Option<u16>,
+    section: &object::Section,
+    base: usize,
+) {
+    for (offset64, relocation) in section.relocations() {
+        let offset = offset64 as usize;
+        if offset as u64 != offset64 {
+            continue;
+        }
+        let offset = base + offset;
+        let unsupported = |problem: &str| {
+            Relocation::Unsupported(format!(
+                "{} relocation ({:?}) in section {} at offset 0x{:08x}",
+                problem,
+                relocation.kind(),
+                section.name().unwrap_or("<unknown>"),
+                offset
+            ))
+        };
+
+        let resolved = match (
+            operation(relocation.kind(), machine),
+            symbol_value(file, relocation.target()),
+        ) {
+            (Some(operation), Ok((symbol, section_address))) => {
+                let value = symbol.wrapping_add(relocation.addend() as u64);
+                match operation {
+                    Operation::Absolute if relocation.has_implicit_addend() => {
+                        Relocation::Add(value)
+                    }
+                    Operation::Absolute => Relocation::Set(value),
+                    Operation::SectionOffset if relocation.has_implicit_addend() => {
+                        Relocation::Add(value.wrapping_sub(section_address))
+                    }
+                    Operation::SectionOffset => {
+                        Relocation::Set(value.wrapping_sub(section_address))
+                    }
+                    Operation::Add => Relocation::Add(value),
+                    Operation::Sub => Relocation::Add(value.wrapping_neg()),
+                }
+            }
+            (Some(_), Err(_)) => unsupported("Invalid symbol for"),
+            (None, _) => unsupported("Unsupported"),
+        };
+
+        let resolved = match relocations.remove(&offset) {
+            Some(existing) => existing.combine(resolved),
+            None => resolved,
+        };
+        relocations.insert(offset, resolved);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Virtuality {
+    Virtual,
+    /// Has no base implementation to fall back on, so it must be overridden. g++ reports pure
+    /// virtual methods as plain virtual, so when reading a header they're found from the vtables
+    /// of derived classes instead (see [`get_vtables_from_header`]).
+    PureVirtual,
+}
+
+/// A method's reference qualifier, e.g. `virtual int x() &&`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RefQualifier {
+    #[default]
+    None,
+    /// `&`
+    LValue,
+    /// `&&`
+    RValue,
+}
+
+/// How a method qualifies `this`, e.g. `virtual int x() const &`. Overloads can differ by these
+/// alone.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Qualifiers {
+    pub is_const: bool,
+    pub is_volatile: bool,
+    pub reference: RefQualifier,
+}
+
+impl fmt::Display for Qualifiers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reference = match self.reference {
+            RefQualifier::None => None,
+            RefQualifier::LValue => Some("&"),
+            RefQualifier::RValue => Some("&&"),
+        };
+        let qualifiers = [
+            Some("const").filter(|_| self.is_const),
+            Some("volatile").filter(|_| self.is_volatile),
+            reference,
+        ];
+        let qualifiers = qualifiers.iter().flatten().copied().collect::<Vec<_>>();
+        f.write_str(&qualifiers.join(" "))
+    }
+}
+
+impl FromStr for Qualifiers {
+    type Err = String;
+
+    /// Parse qualifiers as written after a C++ method's parameters, e.g. `const &&`
+    fn from_str(qualifiers: &str) -> Result<Self, Self::Err> {
+        let mut parsed = Qualifiers::default();
+        // `const&` is also valid C++
+        let qualifiers = qualifiers.replace('&', " & ").replace("&  &", "&&");
+        for qualifier in qualifiers.split_whitespace() {
+            match qualifier {
+                "const" if !parsed.is_const => parsed.is_const = true,
+                "volatile" if !parsed.is_volatile => parsed.is_volatile = true,
+                "&" if parsed.reference == RefQualifier::None => {
+                    parsed.reference = RefQualifier::LValue
+                }
+                "&&" if parsed.reference == RefQualifier::None => {
+                    parsed.reference = RefQualifier::RValue
+                }
+                _ => return Err(format!("invalid qualifier `{}`", qualifier)),
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct VTableElement {
+    pub default: String,
+    pub name: String,
+    pub pos: u64,
+    pub virtuality: Virtuality,
+    pub qualifiers: Qualifiers,
+    /// The method's return type, `None` if it has no `CppType` equivalent or isn't known
+    pub return_type: Option<CppType>,
+    /// The types of the method's parameters after `this`, each `None` if it has no `CppType`
+    /// equivalent
+    pub params: Vec<Option<CppType>>,
+}
+
+impl VTableElement {
+    /// Whether there's no base implementation to fall back on (or no symbol to link against), so
+    /// the method has to be overridden
+    pub fn must_override(&self) -> bool {
+        self.virtuality == Virtuality::PureVirtual || self.default.is_empty()
+    }
+}
+
+/// Swap the variant of an Itanium-mangled constructor (`kind` `C`) or destructor (`kind` `D`), e.g.
+/// `_ZN4baseD4Ev` to `_ZN4baseD0Ev`.
+///
+/// g++ describes constructors and destructors by their unified (`C4`/`D4`) variant, while the
+/// symbols which are called are the complete object (`C1`/`D1`) and deleting (`D0`) ones.
+fn structor_variant(linkage_name: &str, kind: u8, variant: char) -> String {
+    let bytes = linkage_name.as_bytes();
+    let index = (0..bytes.len().saturating_sub(2)).rev().find(|&i| {
+        bytes[i] == kind && (b'0'..=b'5').contains(&bytes[i + 1]) && bytes[i + 2] == b'E'
+    });
+
+    match index {
+        Some(i) => format!(
+            "{}{}{}",
+            &linkage_name[..=i],
+            variant,
+            &linkage_name[i + 2..]
+        ),
+        None => linkage_name.to_owned(),
+    }
+}
+
+fn evaluate_vtable_elem_location<R: gimli::Reader>(
+    expr: gimli::Expression<R>,
+    unit: &gimli::Unit<R>,
+) -> Result<u64, gimli::Error> {
+    let mut pos_eval = expr.evaluation(unit.encoding());
+    let pos_res = pos_eval.evaluate()?;
+    if !matches!(pos_res, gimli::EvaluationResult::Complete) {
+        unimplemented!("{:?}", pos_res);
+    }
+    let pos_pieces = pos_eval.result();
+    let pos_piece = &pos_pieces[0];
+    match pos_piece.location {
+        gimli::Location::Address { address: pos } => Ok(pos),
+        _ => unimplemented!("{:?}", pos_piece),
+    }
+}
+
+/// Put an element in its slot, replacing whatever the base class had there
+fn set_slot(vtable: &mut Vec<VTableElement>, element: VTableElement) {
+    match vtable.iter_mut().find(|entry| entry.pos == element.pos) {
+        Some(entry) => *entry = element,
+        None => vtable.push(element),
+    }
+}
+
+/// Get the vtable of the primary base, which an inheriting class's vtable starts with
+fn get_primary_base_vtable<R: gimli::Reader>(
+    entry: &gimli::DebuggingInformationEntry<R>,
+    unit: &gimli::Unit<R>,
+    dwarf: &gimli::Dwarf<R>,
+    types: &TypeUnits<R>,
+) -> Result<Option<Vec<VTableElement>>, gimli::Error> {
+    // Only a base at the start of the object shares its vtable pointer
+    let offset = entry
+        .attr_value(gimli::DW_AT_data_member_location)?
+        .and_then(|offset| offset.udata_value());
+    if offset != Some(0) {
+        return Ok(None);
+    }
+
+    let (mut unit, mut offset) = match entry.attr_value(gimli::DW_AT_type)? {
+        Some(gimli::AttributeValue::UnitRef(offset)) => (unit, offset),
+        Some(gimli::AttributeValue::DebugTypesRef(signature)) => match types.get(signature) {
+            Some(definition) => definition,
+            None => return Ok(None),
+        },
+        _ => return Ok(None),
+    };
+    // The base may be a declaration whose definition is in a type unit
+    if let Some(gimli::AttributeValue::DebugTypesRef(signature)) =
+        unit.entry(offset)?.attr_value(gimli::DW_AT_signature)?
+    {
+        match types.get(signature) {
+            Some(definition) => (unit, offset) = definition,
+            None => return Ok(None),
+        }
+    }
+    let mut tree = unit.entries_tree(Some(offset))?;
+    get_structure_vtable(tree.root()?, unit, dwarf, types).map(Some)
+}
+
+/// Read how a method qualifies `this`, from its reference qualifier attributes and the type of
+/// its artificial `this` parameter (a pointer to the cv-qualified class), along with the types of
+/// the parameters after it
+fn get_method_signature<'abbrev, 'unit, 'tree, R: gimli::Reader>(
+    node: gimli::EntriesTreeNode<'abbrev, 'unit, 'tree, R>,
+    unit: &gimli::Unit<R>,
+    dwarf: &gimli::Dwarf<R>,
+    types: &TypeUnits<R>,
+) -> Result<(Qualifiers, Vec<Option<CppType>>), gimli::Error> {
+    let entry = node.entry();
+    let reference = if entry.attr_value(gimli::DW_AT_reference)?.is_some() {
+        RefQualifier::LValue
+    } else if entry.attr_value(gimli::DW_AT_rvalue_reference)?.is_some() {
+        RefQualifier::RValue
+    } else {
+        RefQualifier::None
+    };
+    let mut qualifiers = Qualifiers {
+        reference,
+        ..Qualifiers::default()
+    };
+
+    let referenced_type =
+        |entry: &gimli::DebuggingInformationEntry<R>| match entry.attr_value(gimli::DW_AT_type)? {
+            Some(gimli::AttributeValue::UnitRef(offset)) => unit.entry(offset).map(Some),
+            _ => Ok(None),
+        };
+
+    let mut params = Vec::new();
+    let mut children = node.children();
+    while let Some(node) = children.next()? {
+        let entry = node.entry();
+        if entry.tag() != gimli::DW_TAG_formal_parameter {
+            continue;
+        }
+        if entry.attr_value(gimli::DW_AT_artificial)?.is_none() {
+            let ty = resolve_type(entry.attr_value(gimli::DW_AT_type)?, unit, dwarf, types)?;
+            params.push(ty.map(|(ty, _)| ty));
+            continue;
+        }
+
+        // `this` is the first parameter
+        let mut ty = match referenced_type(entry)? {
+            Some(pointer) => referenced_type(&pointer)?,
+            None => None,
+        };
+        while let Some(entry) = ty {
+            match entry.tag() {
+                gimli::DW_TAG_const_type => qualifiers.is_const = true,
+                gimli::DW_TAG_volatile_type => qualifiers.is_volatile = true,
+                _ => break,
+            }
+            ty = referenced_type(&entry)?;
+        }
+    }
+    Ok((qualifiers, params))
+}
+
+fn get_structure_vtable<'abbrev, 'unit, 'tree, R: gimli::Reader>(
+    node: gimli::EntriesTreeNode<'abbrev, 'unit, 'tree, R>,
+    unit: &gimli::Unit<R>,
+    dwarf: &gimli::Dwarf<R>,
+    types: &TypeUnits<R>,
+) -> Result<Vec<VTableElement>, gimli::Error> {
+    let mut vtable = Vec::new();
+    let mut children = node.children();
+    while let Some(node) = children.next()? {
+        let entry = node.entry();
+        if entry.tag() == gimli::DW_TAG_inheritance && vtable.is_empty() {
+            if let Some(base_vtable) = get_primary_base_vtable(entry, unit, dwarf, types)? {
+                vtable = base_vtable;
+            }
+        } else if entry.tag() == gimli::DW_TAG_subprogram {
+            let virtuality = match entry.attr_value(gimli::DW_AT_virtuality)? {
+                Some(gimli::AttributeValue::Virtuality(gimli::DW_VIRTUALITY_pure_virtual)) => {
+                    Virtuality::PureVirtual
+                }
+                Some(gimli::AttributeValue::Virtuality(gimli::DW_VIRTUALITY_virtual)) => {
+                    Virtuality::Virtual
+                }
+                _ => continue,
+            };
+            let (name_val, default_val) = match (
+                entry.attr_value(gimli::DW_AT_name)?,
+                entry.attr_value(gimli::DW_AT_linkage_name)?,
+            ) {
+                (Some(name_val), Some(default_val)) => (name_val, default_val),
+                _ => continue,
+            };
+            let name_bytes = dwarf.attr_string(unit, name_val)?;
+            let name = gimli::Reader::to_string(&name_bytes)?.to_string();
+            let default_bytes = dwarf.attr_string(unit, default_val)?;
+            let default = gimli::Reader::to_string(&default_bytes)?.to_string();
+            let pos = match entry
+                .attr_value(gimli::DW_AT_vtable_elem_location)?
+                .and_then(|x| x.exprloc_value())
+            {
+                Some(pos_expr) => Some(evaluate_vtable_elem_location(pos_expr, unit)?),
+                None => None,
+            };
+            let return_type =
+                resolve_type(entry.attr_value(gimli::DW_AT_type)?, unit, dwarf, types)?
+                    .map(|(ty, _)| ty);
+            let (qualifiers, params) = get_method_signature(node, unit, dwarf, types)?;
+
+            if name.starts_with('~') {
+                // A virtual destructor takes up two slots: the complete object destructor followed
+                // by the deleting destructor. g++ doesn't give them a location, so they either
+                // replace the base's destructor or go after every slot declared so far.
+                let pos = pos
+                    .or_else(|| {
+                        vtable
+                            .iter()
+                            .find(|entry| entry.name.starts_with('~'))
+                            .map(|entry| entry.pos)
+                    })
+                    .unwrap_or_else(|| vtable.iter().map(|entry| entry.pos + 1).max().unwrap_or(0));
+
+                for (pos, variant) in [(pos, '1'), (pos + 1, '0')] {
+                    set_slot(
+                        &mut vtable,
+                        VTableElement {
+                            name: name.clone(),
+                            default: structor_variant(&default, b'D', variant),
+                            pos,
+                            virtuality,
+                            qualifiers,
+                            return_type: Some(CppType::Void),
+                            params: Vec::new(),
+                        },
+                    );
+                }
+            } else if let Some(pos) = pos {
+                set_slot(
+                    &mut vtable,
+                    VTableElement {
+                        name,
+                        default,
+                        pos,
+                        virtuality,
+                        qualifiers,
+                        return_type,
+                        params,
+                    },
+                );
+            }
+        }
+    }
+    vtable.sort_by_key(|entry| entry.pos);
+    Ok(vtable)
+}
+
+/// Resolve the type a `DW_AT_type` refers to, along with whether it's `const`. `None` if it isn't
+/// one which can be described, e.g. a function pointer or array.
+fn resolve_type<R: gimli::Reader>(
+    value: Option<gimli::AttributeValue<R>>,
+    unit: &gimli::Unit<R>,
+    dwarf: &gimli::Dwarf<R>,
+    types: &TypeUnits<R>,
+) -> Result<Option<(CppType, bool)>, gimli::Error> {
+    let (unit, offset) = match value {
+        // A missing type is `void`
+        None => return Ok(Some((CppType::Void, false))),
+        Some(gimli::AttributeValue::UnitRef(offset)) => (unit, offset),
+        Some(gimli::AttributeValue::DebugTypesRef(signature)) => match types.get(signature) {
+            Some(definition) => definition,
+            None => return Ok(None),
+        },
+        _ => return Ok(None),
+    };
+    let entry = unit.entry(offset)?;
+    let inner = entry.attr_value(gimli::DW_AT_type)?;
+
+    let ty = match entry.tag() {
+        gimli::DW_TAG_base_type => {
+            let size = entry
+                .attr_value(gimli::DW_AT_byte_size)?
+                .and_then(|size| size.udata_value())
+                .unwrap_or(0) as u8;
+            match entry.attr_value(gimli::DW_AT_encoding)? {
+                Some(gimli::AttributeValue::Encoding(gimli::DW_ATE_boolean)) => CppType::Bool,
+                Some(gimli::AttributeValue::Encoding(gimli::DW_ATE_float)) => {
+                    CppType::Float { size }
+                }
+                Some(gimli::AttributeValue::Encoding(
+                    gimli::DW_ATE_signed | gimli::DW_ATE_signed_char,
+                )) => CppType::Int { size, signed: true },
+                Some(gimli::AttributeValue::Encoding(
+                    gimli::DW_ATE_unsigned | gimli::DW_ATE_unsigned_char | gimli::DW_ATE_UTF,
+                )) => CppType::Int {
+                    size,
+                    signed: false,
+                },
+                _ => return Ok(None),
+            }
+        }
+        gimli::DW_TAG_const_type => {
+            return Ok(resolve_type(inner, unit, dwarf, types)?.map(|(ty, _)| (ty, true)))
+        }
+        gimli::DW_TAG_typedef | gimli::DW_TAG_volatile_type => {
+            return resolve_type(inner, unit, dwarf, types)
+        }
+        gimli::DW_TAG_pointer_type => match resolve_type(inner, unit, dwarf, types)? {
+            Some((pointee, is_const)) => CppType::Pointer {
+                pointee: Box::new(pointee),
+                is_const,
+            },
+            None => return Ok(None),
+        },
+        gimli::DW_TAG_reference_type | gimli::DW_TAG_rvalue_reference_type => {
+            match resolve_type(inner, unit, dwarf, types)? {
+                Some((referent, is_const)) => CppType::Reference {
+                    referent: Box::new(referent),
+                    is_const,
+                },
+                None => return Ok(None),
+            }
+        }
+        gimli::DW_TAG_structure_type
+        | gimli::DW_TAG_class_type
+        | gimli::DW_TAG_union_type
+        | gimli::DW_TAG_enumeration_type => match entry.attr_value(gimli::DW_AT_name)? {
+            Some(name) => {
+                let name = dwarf.attr_string(unit, name)?;
+                CppType::Named(gimli::Reader::to_string(&name)?.to_string())
+            }
+            None => return Ok(None),
+        },
+        _ => return Ok(None),
+    };
+    Ok(Some((ty, false)))
+}
+
+/// Get the constructors a class declares, other than those which are deleted or implicit (the
+/// compiler only emits implicit ones where they're used). Constructors with a parameter type
+/// which can't be described are left out.
+fn get_structure_constructors<'abbrev, 'unit, 'tree, R: gimli::Reader>(
+    name: &str,
+    node: gimli::EntriesTreeNode<'abbrev, 'unit, 'tree, R>,
+    unit: &gimli::Unit<R>,
+    dwarf: &gimli::Dwarf<R>,
+    types: &TypeUnits<R>,
+) -> Result<Vec<Constructor>, gimli::Error> {
+    // Constructors of a template are named without the template arguments
+    let name = name.split('<').next().unwrap_or(name);
+
+    let mut constructors = Vec::new();
+    let mut children = node.children();
+    'constructors: while let Some(node) = children.next()? {
+        let entry = node.entry();
+        if entry.tag() != gimli::DW_TAG_subprogram
+            || entry.attr_value(gimli::DW_AT_artificial)?.is_some()
+            || entry.attr_value(gimli::DW_AT_deleted)?.is_some()
+        {
+            continue;
+        }
+        let (name_val, linkage_val) = match (
+            entry.attr_value(gimli::DW_AT_name)?,
+            entry.attr_value(gimli::DW_AT_linkage_name)?,
+        ) {
+            (Some(name_val), Some(linkage_val)) => (name_val, linkage_val),
+            _ => continue,
+        };
+        if gimli::Reader::to_string(&dwarf.attr_string(unit, name_val)?)? != name {
+            continue;
+        }
+        let linkage_name = dwarf.attr_string(unit, linkage_val)?;
+        let symbol = structor_variant(&gimli::Reader::to_string(&linkage_name)?, b'C', '1');
+
+        let mut params = Vec::new();
+        let mut children = node.children();
+        while let Some(node) = children.next()? {
+            let entry = node.entry();
+            // Variadic constructors can't be declared in Rust
+            if entry.tag() == gimli::DW_TAG_unspecified_parameters {
+                continue 'constructors;
+            }
+            if entry.tag() != gimli::DW_TAG_formal_parameter
+                || entry.attr_value(gimli::DW_AT_artificial)?.is_some()
+            {
+                continue;
+            }
+            match resolve_type(entry.attr_value(gimli::DW_AT_type)?, unit, dwarf, types)? {
+                Some((ty, _)) => params.push(ty),
+                None => continue 'constructors,
+            }
+        }
+
+        constructors.push(Constructor { symbol, params });
+    }
+    Ok(constructors)
+}
+
+/// Get a class' direct bases
+fn get_structure_bases<'abbrev, 'unit, 'tree, R: gimli::Reader>(
+    node: gimli::EntriesTreeNode<'abbrev, 'unit, 'tree, R>,
+    unit: &gimli::Unit<R>,
+    dwarf: &gimli::Dwarf<R>,
+    types: &TypeUnits<R>,
+) -> Result<Vec<BaseClass>, gimli::Error> {
+    let mut bases = Vec::new();
+    let mut children = node.children();
+    while let Some(node) = children.next()? {
+        let entry = node.entry();
+        if entry.tag() != gimli::DW_TAG_inheritance {
+            continue;
+        }
+        let name = match field_class(entry.attr_value(gimli::DW_AT_type)?, unit, dwarf, types)? {
+            Some(name) => name,
+            None => continue,
+        };
+        // A virtual base's location is an expression reading its offset from the vtable
+        let offset = if entry.attr_value(gimli::DW_AT_virtuality)?.is_some() {
+            None
+        } else {
+            entry
+                .attr_value(gimli::DW_AT_data_member_location)?
+                .and_then(|offset| offset.udata_value())
+        };
+        bases.push(BaseClass { name, offset });
+    }
+    Ok(bases)
+}
+
+/// What decides whether a class is trivial for the purposes of calls, in which case it's passed and
+/// returned like a C struct rather than through a pointer to a temporary
+#[derive(Debug, Default)]
+struct CallTriviality {
+    /// Whether the class itself makes it non-trivial: it's polymorphic, has a virtual base, has
+    /// a user-provided copy constructor, move constructor or destructor, or has copy and move
+    /// constructors which are all deleted
+    non_trivial: bool,
+    /// The classes of its bases and members, which make it non-trivial if they are
+    fields: Vec<String>,
+}
+
+/// The class a base or member is (or is an array of), if it is one
+fn field_class<R: gimli::Reader>(
+    value: Option<gimli::AttributeValue<R>>,
+    unit: &gimli::Unit<R>,
+    dwarf: &gimli::Dwarf<R>,
+    types: &TypeUnits<R>,
+) -> Result<Option<String>, gimli::Error> {
+    if let Some(gimli::AttributeValue::UnitRef(offset)) = value {
+        let entry = unit.entry(offset)?;
+        if entry.tag() == gimli::DW_TAG_array_type {
+            return field_class(entry.attr_value(gimli::DW_AT_type)?, unit, dwarf, types);
+        }
+    }
+    Ok(match resolve_type(value, unit, dwarf, types)? {
+        Some((CppType::Named(name), _)) => Some(name),
+        _ => None,
+    })
+}
+
+/// Read what decides whether a class is trivial for the purposes of calls. Implicit members are
+/// only emitted where they're used, so they're treated as trivial unless a base or member isn't.
+fn get_structure_triviality<'abbrev, 'unit, 'tree, R: gimli::Reader>(
+    name: &str,
+    node: gimli::EntriesTreeNode<'abbrev, 'unit, 'tree, R>,
+    unit: &gimli::Unit<R>,
+    dwarf: &gimli::Dwarf<R>,
+    types: &TypeUnits<R>,
+) -> Result<CallTriviality, gimli::Error> {
+    // Constructors of a template are named without the template arguments
+    let constructor_name = name.split('<').next().unwrap_or(name);
+    let copy_types = [true, false].map(|is_const| CppType::Reference {
+        referent: Box::new(CppType::Named(name.to_owned())),
+        is_const,
+    });
+
+    let mut triviality = CallTriviality::default();
+    let mut copy_constructors = 0;
+    let mut deleted_copy_constructors = 0;
+    let mut children = node.children();
+    while let Some(node) = children.next()? {
+        let entry = node.entry();
+        match entry.tag() {
+            gimli::DW_TAG_inheritance => {
+                if entry.attr_value(gimli::DW_AT_virtuality)?.is_some() {
+                    triviality.non_trivial = true;
+                }
+                let base = field_class(entry.attr_value(gimli::DW_AT_type)?, unit, dwarf, types)?;
+                triviality.fields.extend(base);
+            }
+            // Static members are declarations (in DWARF 4, DWARF 5 makes them variables)
+            gimli::DW_TAG_member if entry.attr_value(gimli::DW_AT_declaration)?.is_none() => {
+                let member = field_class(entry.attr_value(gimli::DW_AT_type)?, unit, dwarf, types)?;
+                triviality.fields.extend(member);
+            }
+            gimli::DW_TAG_subprogram => {
+                if entry.attr_value(gimli::DW_AT_virtuality)?.is_some() {
+                    triviality.non_trivial = true;
+                    continue;
+                }
+                let method_name = match entry.attr_value(gimli::DW_AT_name)? {
+                    Some(name_val) => dwarf.attr_string(unit, name_val)?,
+                    None => continue,
+                };
+                let method_name = gimli::Reader::to_string(&method_name)?;
+                let is_deleted = entry.attr_value(gimli::DW_AT_deleted)?.is_some();
+                let defaulted_in_class = entry
+                    .attr_value(gimli::DW_AT_defaulted)?
+                    .and_then(|defaulted| defaulted.udata_value())
+                    == Some(u64::from(gimli::DW_DEFAULTED_in_class.0));
+                let user_provided = !is_deleted
+                    && !defaulted_in_class
+                    && entry.attr_value(gimli::DW_AT_artificial)?.is_none();
+
+                if method_name.starts_with('~') {
+                    triviality.non_trivial |= user_provided;
+                } else if method_name == constructor_name {
+                    let mut params = Vec::new();
+                    let mut children = node.children();
+                    while let Some(node) = children.next()? {
+                        let entry = node.entry();
+                        if entry.tag() == gimli::DW_TAG_formal_parameter
+                            && entry.attr_value(gimli::DW_AT_artificial)?.is_none()
+                        {
+                            let ty = entry.attr_value(gimli::DW_AT_type)?;
+                            params.push(resolve_type(ty, unit, dwarf, types)?.map(|(ty, _)| ty));
+                        }
+                    }
+
+                    // Copy and move constructors both take a reference to the class
+                    if let [Some(param)] = &params[..] {
+                        if copy_types.contains(param) {
+                            copy_constructors += 1;
+                            if is_deleted {
+                                deleted_copy_constructors += 1;
+                            }
+                            triviality.non_trivial |= user_provided;
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    if copy_constructors > 0 && copy_constructors == deleted_copy_constructors {
+        triviality.non_trivial = true;
+    }
+    Ok(triviality)
+}
+
+/// The classes which are non-trivial for the purposes of calls, either themselves or because one
+/// of their bases or members is
+fn non_trivial_classes(triviality: &HashMap<String, CallTriviality>) -> HashSet<String> {
+    let mut non_trivial = triviality
+        .iter()
+        .filter(|(_, class)| class.non_trivial)
+        .map(|(name, _)| name.clone())
+        .collect::<HashSet<_>>();
+    loop {
+        let found = triviality
+            .iter()
+            .filter(|(name, class)| {
+                !non_trivial.contains(*name)
+                    && class.fields.iter().any(|field| non_trivial.contains(field))
+            })
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<_>>();
+        if found.is_empty() {
+            return non_trivial;
+        }
+        non_trivial.extend(found);
+    }
+}
+
+/// Type units (`-fdebug-types-section`), by the signature other units refer to their type with
+struct TypeUnits<R: gimli::Reader> {
+    units: Vec<gimli::Unit<R>>,
+    signatures: HashMap<gimli::DebugTypeSignature, (usize, gimli::UnitOffset<R::Offset>)>,
+}
+
+impl<R: gimli::Reader> TypeUnits<R> {
+    /// Load the type units from `.debug_types` (DWARF 4) and `.debug_info` (DWARF 5)
+    fn load(dwarf: &gimli::Dwarf<R>) -> Result<Self, gimli::Error> {
+        let mut headers = Vec::new();
+        let mut iter = dwarf.type_units();
+        while let Some(header) = iter.next()? {
+            headers.push(header);
+        }
+        let mut iter = dwarf.units();
+        while let Some(header) = iter.next()? {
+            headers.push(header);
+        }
+
+        let mut units = Vec::new();
+        let mut signatures = HashMap::new();
+        for header in headers {
+            match header.type_() {
+                gimli::UnitType::Type {
+                    type_signature,
+                    type_offset,
+                }
+                | gimli::UnitType::SplitType {
+                    type_signature,
+                    type_offset,
+                } => {
+                    signatures.insert(type_signature, (units.len(), type_offset));
+                    units.push(dwarf.unit(header)?);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(Self { units, signatures })
+    }
+
+    fn get(
+        &self,
+        signature: gimli::DebugTypeSignature,
+    ) -> Option<(&gimli::Unit<R>, gimli::UnitOffset<R::Offset>)> {
+        let (index, offset) = *self.signatures.get(&signature)?;
+        Some((&self.units[index], offset))
+    }
+}
+
+/// What's read about each class defined in the debug info
+#[derive(Default)]
+struct Classes {
+    vtables: HashMap<String, Vec<VTableElement>>,
+    constructors: HashMap<String, Vec<Constructor>>,
+    triviality: HashMap<String, CallTriviality>,
+    bases: HashMap<String, Vec<BaseClass>>,
+    /// The name of each class including its namespaces, e.g. `shapes::shape`
+    qualified_names: HashMap<String, String>,
+}
+
+fn walk_node<'abbrev, 'unit, 'tree, R: gimli::Reader>(
+    node: gimli::EntriesTreeNode<'abbrev, 'unit, 'tree, R>,
+    unit: &gimli::Unit<R>,
+    dwarf: &gimli::Dwarf<R>,
+    types: &TypeUnits<R>,
+    classes: &mut Classes,
+    scope: &str,
+) -> Result<(), gimli::Error> {
+    let entry = node.entry();
+
+    if entry.tag() == gimli::DW_TAG_structure_type || entry.tag() == gimli::DW_TAG_class_type {
+        // Declarations (including ones whose `DW_AT_signature` points at the definition in a type
+        // unit) have no members, so they'd replace the real vtable with an empty one
+        if entry.attr_value(gimli::DW_AT_declaration)?.is_some() {
+            return Ok(());
+        }
+        let name_val = if let Some(name_val) = entry.attr_value(gimli::DW_AT_name)? {
+            name_val
+        } else {
+            return Ok(());
+        };
+        let name_bytes = dwarf.attr_string(unit, name_val)?;
+        let name = gimli::Reader::to_string(&name_bytes)?.to_string();
+
+        let offset = entry.offset();
+        let vtable = get_structure_vtable(node, unit, dwarf, types)?;
+        let mut tree = unit.entries_tree(Some(offset))?;
+        let constructors = get_structure_constructors(&name, tree.root()?, unit, dwarf, types)?;
+        let mut tree = unit.entries_tree(Some(offset))?;
+        let triviality = get_structure_triviality(&name, tree.root()?, unit, dwarf, types)?;
+        let mut tree = unit.entries_tree(Some(offset))?;
+        let bases = get_structure_bases(tree.root()?, unit, dwarf, types)?;
+
+        classes.vtables.insert(name.clone(), vtable);
+        classes.constructors.insert(name.clone(), constructors);
+        classes.triviality.insert(name.clone(), triviality);
+        classes.bases.insert(name.clone(), bases);
+        classes
+            .qualified_names
+            .insert(name.clone(), format!("{}{}", scope, name));
+    } else {
+        // Anonymous namespaces don't need naming, as the probe includes the header
+        let mut scope = scope.to_owned();
+        if entry.tag() == gimli::DW_TAG_namespace {
+            if let Some(name) = entry.attr_value(gimli::DW_AT_name)? {
+                let name = dwarf.attr_string(unit, name)?;
+                scope = format!("{}{}::", scope, gimli::Reader::to_string(&name)?);
+            }
+        }
+        let mut children = node.children();
+        while let Some(node) = children.next()? {
+            walk_node(node, unit, dwarf, types, classes, &scope)?;
+        }
+    }
+
+    Ok(())
+}
+
+type SectionReader<'a> = Relocate<'a, gimli::EndianSlice<'a, gimli::RunTimeEndian>>;
+
+/// Owns the (possibly decompressed) section data and relocations the readers borrow from
+#[derive(Default)]
+struct SectionArena<'a> {
+    data: Arena<Cow<'a, [u8]>>,
+    relocations: Arena<RelocationMap>,
+}
+
+/// Load a section and its relocations. Sections that are missing load as empty.
+fn load_section<'a>(
+    arena: &'a SectionArena<'a>,
+    object: &object::File<'a>,
+    machine: Option<u16>,
+    name: Option<&str>,
+) -> Result<SectionReader<'a>, object::read::Error> {
+    let endian = if object.is_little_endian() {
+        gimli::RunTimeEndian::Little
+    } else {
+        gimli::RunTimeEndian::Big
+    };
+    let mut relocations = RelocationMap::default();
+    // Unlinked objects put each type unit in its own COMDAT section, so all the sections with the
+    // name are concatenated. `.zdebug_*` sections are also matched, and both forms of compression
+    // are undone by `uncompressed_data`.
+    let mut sections = object
+        .sections()
+        .filter(|section| match (section.name(), name) {
+            (Ok(section_name), Some(name)) => {
+                section_name == name || section_name.strip_prefix(".z") == name.strip_prefix('.')
+            }
+            _ => false,
+        })
+        .collect::<Vec<_>>();
+    let data = match sections.len() {
+        // Use a non-zero capacity so that `ReaderOffsetId`s are unique.
+        0 => Cow::Owned(Vec::with_capacity(1)),
+        1 => {
+            let section = sections.remove(0);
+            add_relocations(&mut relocations, object, machine, &section, 0);
+            section.uncompressed_data()?
+        }
+        _ => {
+            let mut data = Vec::new();
+            for section in &sections {
+                add_relocations(&mut relocations, object, machine, section, data.len());
+                data.extend_from_slice(&section.uncompressed_data()?);
+            }
+            Cow::Owned(data)
+        }
+    };
+    let data_ref = (*arena.data.alloc(data)).borrow();
+    let reader = gimli::EndianSlice::new(data_ref, endian);
+    let section = reader;
+    let relocations = (*arena.relocations.alloc(relocations)).borrow();
+    Ok(Relocate {
+        relocations,
+        section,
+        reader,
+    })
+}
+
+/// Loads the split debug info a skeleton unit refers to
+type SplitDwarfLoader<'a, R> =
+    dyn FnMut(&gimli::Unit<R>) -> Result<Option<gimli::Dwarf<R>>, gimli::Error> + 'a;
+
+fn walk_units<R: gimli::Reader>(
+    dwarf: &gimli::Dwarf<R>,
+    classes: &mut Classes,
+    split_dwarf: &mut SplitDwarfLoader<R>,
+) -> Result<(), gimli::Error> {
+    let types = TypeUnits::load(dwarf)?;
+    for unit in &types.units {
+        let mut tree = unit.entries_tree(None)?;
+        walk_node(tree.root()?, unit, dwarf, &types, classes, "")?;
+    }
+
+    // Iterate over the compilation units.
+    let mut iter = dwarf.units();
+    while let Some(header) = iter.next()? {
+        if let gimli::UnitType::Type { .. } | gimli::UnitType::SplitType { .. } = header.type_() {
+            continue;
+        }
+        let unit = dwarf.unit(header)?;
+
+        // Skeleton units only point at the .dwo/.dwp holding the actual debug info
+        if unit.dwo_id.is_some() && dwarf.file_type == gimli::DwarfFileType::Main {
+            if let Some(split) = split_dwarf(&unit)? {
+                walk_units(&split, classes, &mut |_| Ok(None))?;
+            }
+            continue;
+        }
+
+        let mut tree = unit.entries_tree(None)?;
+        let root = tree.root()?;
+        walk_node(root, &unit, dwarf, &types, classes, "")?;
+    }
+
+    Ok(())
+}
+
+/// Find the split debug info for a skeleton unit, from either a package (`.dwp`) next to the
+/// object or the `.dwo` the unit names. The `.dwo` is looked for relative to the unit's
+/// compilation directory first, then next to the object in case the build tree was moved.
+fn find_split_dwarf<'a>(
+    arena: &'a SectionArena<'a>,
+    files: &'a Arena<memmap::Mmap>,
+    path: &Path,
+    dwarf: &gimli::Dwarf<SectionReader<'a>>,
+    unit: &gimli::Unit<SectionReader<'a>>,
+) -> Result<Option<gimli::Dwarf<SectionReader<'a>>>, gimli::Error> {
+    let dwo_id = match unit.dwo_id {
+        Some(dwo_id) => dwo_id,
+        None => return Ok(None),
+    };
+
+    let open = |path: &Path| -> Option<(object::File<'a>, Option<u16>)> {
+        let file = fs::File::open(path).ok()?;
+        let mmap = &**files.alloc(unsafe { memmap::Mmap::map(&file).ok()? });
+        Some((object::File::parse(mmap).ok()?, elf_machine(mmap)))
+    };
+    fn load_dwo<'a, 'b>(
+        arena: &'a SectionArena<'a>,
+        object: &'b object::File<'a>,
+        machine: Option<u16>,
+    ) -> impl Fn(gimli::SectionId) -> Result<SectionReader<'a>, gimli::Error> + 'b {
+        move |id| load_section(arena, object, machine, id.dwo_name()).map_err(|_| gimli::Error::Io)
+    }
+
+    if let Some((package, machine)) = open(&path.with_extension("dwp")) {
+        let empty = load_section(arena, &package, machine, None).map_err(|_| gimli::Error::Io)?;
+        let package = gimli::DwarfPackage::load(load_dwo(arena, &package, machine), empty)?;
+        if let Some(split) = package.find_cu(dwo_id, dwarf)? {
+            return Ok(Some(split));
+        }
+    }
+
+    use gimli::Reader;
+
+    let dwo_name = match unit.dwo_name()? {
+        Some(name) => dwarf.attr_string(unit, name)?.to_string()?.into_owned(),
+        None => return Ok(None),
+    };
+    let comp_dir = match unit.comp_dir {
+        Some(ref comp_dir) => Path::new(&*comp_dir.to_string()?).join(&dwo_name),
+        None => dwo_name.clone().into(),
+    };
+    let next_to_object = path.with_file_name(Path::new(&dwo_name).file_name().unwrap_or_default());
+
+    let (dwo, machine) = match open(&comp_dir).or_else(|| open(&next_to_object)) {
+        Some(dwo) => dwo,
+        None => panic!("Could not find split debug info {}", dwo_name),
+    };
+    let mut split = gimli::Dwarf::load(load_dwo(arena, &dwo, machine))?;
+    split.make_dwo(dwarf);
+
+    Ok(Some(split))
+}
+
+fn dump_file(
+    path: &Path,
+    object: &object::File,
+    machine: Option<u16>,
+) -> Result<Classes, gimli::Error> {
+    let files = Arena::new();
+    let arena = SectionArena::default();
+
+    let dwarf = gimli::Dwarf::load(|id| load_section(&arena, object, machine, Some(id.name())))
+        .map_err(|_| gimli::Error::Io)?;
+
+    let mut classes = Classes::default();
+    walk_units(&dwarf, &mut classes, &mut |unit| {
+        find_split_dwarf(&arena, &files, path, &dwarf, unit)
+    })?;
+
+    Ok(classes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BaseOffset;
+
+    fn fixture(name: &str) -> std::path::PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name)
+    }
+
+    fn layout<'a>(info: &'a VTableInfo, class: &str) -> Vec<(u64, &'a str)> {
+        info.get(class)
+            .unwrap()
+            .iter()
+            .map(|entry| (entry.pos, entry.default.as_str()))
+            .collect()
+    }
+
+    /// The vtables g++ emits for tests/fixtures/vtables.hpp (`_ZTV4base`, `_ZTV7derived`),
+    /// excluding the offset-to-top and typeinfo, written out by hand. The fixtures for other
+    /// architectures are reassembled from g++'s x86 output (there's no cross compiler to build
+    /// them with), so these don't depend on the target.
+    fn assert_golden_vtables(info: &VTableInfo) {
+        assert_eq!(
+            layout(info, "base"),
+            [
+                (0, "_ZN4base1xEv"),
+                (1, "_ZN4baseD1Ev"),
+                (2, "_ZN4baseD0Ev"),
+                (3, "_ZN4base1yEi"),
+            ]
+        );
+        assert_eq!(
+            layout(info, "derived"),
+            [
+                (0, "_ZN4base1xEv"),
+                (1, "_ZN7derivedD1Ev"),
+                (2, "_ZN7derivedD0Ev"),
+                (3, "_ZN7derived1yEi"),
+                (4, "_ZN7derived1zEv"),
+            ]
+        );
+    }
+
+    #[test]
+    fn armv7_vtables() {
+        let info = get_vtables_from_file(&fixture("armv7.o"), "armv7-unknown-linux-gnueabihf");
+        assert_eq!(info.architecture, object::Architecture::Arm);
+        assert_golden_vtables(&info);
+    }
+
+    #[test]
+    fn thumbv7em_vtables() {
+        let info = get_vtables_from_file(&fixture("thumbv7em.o"), "thumbv7em-none-eabihf");
+        assert_golden_vtables(&info);
+    }
+
+    #[test]
+    fn armebv7r_vtables() {
+        let info = get_vtables_from_file(&fixture("armebv7r.o"), "armebv7r-none-eabi");
+        assert_eq!(info.address_size, 4);
+        assert_golden_vtables(&info);
+    }
+
+    #[test]
+    fn powerpc_vtables() {
+        let info = get_vtables_from_file(&fixture("powerpc.o"), "powerpc-unknown-linux-gnu");
+        assert_eq!(info.address_size, 4);
+        assert_golden_vtables(&info);
+    }
+
+    #[test]
+    fn powerpc64_vtables() {
+        let info = get_vtables_from_file(&fixture("powerpc64.o"), "powerpc64-unknown-linux-gnu");
+        assert_eq!(info.address_size, 8);
+        assert_golden_vtables(&info);
+    }
+
+    #[test]
+    fn mips_vtables() {
+        let info = get_vtables_from_file(&fixture("mips.o"), "mips-unknown-linux-gnu");
+        assert_eq!(info.address_size, 4);
+        assert_golden_vtables(&info);
+    }
+
+    #[test]
+    fn riscv32_vtables() {
+        let info = get_vtables_from_file(&fixture("riscv32.o"), "riscv32gc-unknown-linux-gnu");
+        assert_eq!(info.address_size, 4);
+        assert_golden_vtables(&info);
+    }
+
+    #[test]
+    fn riscv64_vtables() {
+        let info = get_vtables_from_file(&fixture("riscv64.o"), "riscv64gc-unknown-linux-gnu");
+        assert_eq!(info.address_size, 8);
+        assert_golden_vtables(&info);
+    }
+
+    const HOST: &str = "x86_64-unknown-linux-gnu";
+
+    #[test]
+    fn dwarf5_vtables() {
+        assert_golden_vtables(&get_vtables_from_file(&fixture("dwarf5.o"), HOST));
+    }
+
+    #[test]
+    fn split_dwarf5_vtables() {
+        assert_golden_vtables(&get_vtables_from_file(&fixture("split-dwarf5.o"), HOST));
+    }
+
+    #[test]
+    fn split_gnu_dwarf4_vtables() {
+        assert_golden_vtables(&get_vtables_from_file(&fixture("split-gnu.o"), HOST));
+    }
+
+    #[test]
+    fn split_dwarf_package_vtables() {
+        assert_golden_vtables(&get_vtables_from_file(&fixture("split-package.o"), HOST));
+    }
+
+    #[test]
+    fn type_unit_dwarf4_vtables() {
+        assert_golden_vtables(&get_vtables_from_file(&fixture("types-dwarf4.o"), HOST));
+    }
+
+    #[test]
+    fn type_unit_dwarf5_vtables() {
+        assert_golden_vtables(&get_vtables_from_file(&fixture("types-dwarf5.o"), HOST));
+    }
+
+    #[test]
+    fn split_type_unit_vtables() {
+        assert_golden_vtables(&get_vtables_from_file(&fixture("types-split.o"), HOST));
+    }
+
+    #[test]
+    fn compressed_vtables() {
+        assert_golden_vtables(&get_vtables_from_file(&fixture("compressed.o"), HOST));
+    }
+
+    #[test]
+    fn zdebug_vtables() {
+        assert_golden_vtables(&get_vtables_from_file(&fixture("compressed-gnu.o"), HOST));
+    }
+
+    /// The constructors in tests/fixtures/constructors.hpp which can be called from Rust, i.e.
+    /// without the deleted, variadic and function pointer taking ones
+    fn assert_constructors(info: &VTableInfo) {
+        let named = |name: &str| Box::new(CppType::Named(name.to_owned()));
+        let int = |size, signed| CppType::Int { size, signed };
+        let constructor = |symbol: &str, params| Constructor {
+            symbol: symbol.to_owned(),
+            params,
+        };
+
+        assert_eq!(
+            info.constructors("widget"),
+            [
+                constructor("_ZN6widgetC1Ev", vec![]),
+                constructor(
+                    "_ZN6widgetC1ER7managerPKcj",
+                    vec![
+                        CppType::Reference {
+                            referent: named("manager"),
+                            is_const: false,
+                        },
+                        CppType::Pointer {
+                            pointee: Box::new(int(1, true)),
+                            is_const: true,
+                        },
+                        int(4, false),
+                    ],
+                ),
+                constructor(
+                    "_ZN6widgetC1EPKS_4kinddbm",
+                    vec![
+                        CppType::Pointer {
+                            pointee: named("widget"),
+                            is_const: true,
+                        },
+                        CppType::Named("kind".to_owned()),
+                        CppType::Float { size: 8 },
+                        CppType::Bool,
+                        int(8, false),
+                    ],
+                ),
+            ]
+        );
+        assert_eq!(
+            info.constructors("holder<short int>"),
+            [constructor("_ZN6holderIsEC1Es", vec![int(2, true)])]
+        );
+    }
+
+    #[test]
+    fn constructors() {
+        assert_constructors(&get_vtables_from_file(&fixture("constructors.o"), HOST));
+    }
+
+    #[test]
+    fn type_unit_constructors() {
+        assert_constructors(&get_vtables_from_file(
+            &fixture("constructors-types.o"),
+            HOST,
+        ));
+    }
+
+    #[test]
+    fn method_qualifiers() {
+        let info = get_vtables_from_file(&fixture("qualifiers.o"), HOST);
+        let vtable = info
+            .classes
+            .get("qualified")
+            .expect("qualified wasn't found");
+
+        let slots = vtable
+            .iter()
+            .map(|entry| (entry.pos, entry.name.as_str(), entry.qualifiers.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            slots,
+            vec![
+                (0, "x", "".to_owned()),
+                (1, "x", "const".to_owned()),
+                (2, "y", "volatile".to_owned()),
+                (3, "y", "const volatile".to_owned()),
+                (4, "z", "&".to_owned()),
+                (5, "z", "const &&".to_owned()),
+            ]
+        );
+        assert_eq!(vtable[4].default, "_ZNR9qualified1zEv");
+        assert_eq!(vtable[5].default, "_ZNKO9qualified1zEv");
+    }
+
+    #[test]
+    fn pure_virtuals() {
+        fn must_override<'a>(info: &'a VTableInfo, class: &str) -> Vec<&'a str> {
+            info.get(class)
+                .unwrap()
+                .iter()
+                .filter(|entry| entry.must_override())
+                .map(|entry| entry.name.as_str())
+                .collect()
+        }
+
+        // g++ describes them as plain virtual methods with a symbol, which doesn't exist
+        let info = get_vtables_from_file(&fixture("abstract.o"), HOST);
+        assert!(must_override(&info, "shape").is_empty());
+        assert_eq!(
+            info.get("shape").unwrap()[2].default,
+            "_ZNK6shapes5shape4areaEv"
+        );
+
+        let mut probes = Vec::new();
+        let info = get_vtables_from_header(
+            &fixture("abstract.o"),
+            Path::new("abstract.hpp"),
+            HOST,
+            |source| {
+                probes.push(source.to_owned());
+                Some(fixture("abstract-probe.o"))
+            },
+        );
+        assert_eq!(
+            probes,
+            [fs::read_to_string(fixture("abstract-probe.cpp")).unwrap()]
+        );
+        assert_eq!(must_override(&info, "shape"), ["area", "sides"]);
+        assert_eq!(must_override(&info, "square"), ["sides"]);
+        assert!(must_override(&info, "concrete").is_empty());
+    }
+
+    #[test]
+    fn arm_pure_virtuals() {
+        // The probe's vtables from llc's ARM and Thumb output, with REL relocations (the addend
+        // is in the section) against functions whose symbols have the Thumb bit set. The ARM C++
+        // ABI fills pure virtual slots with `__cxa_pure_virtual` too, its `__aeabi_` helpers being
+        // for array cookies and static destructors, which the macros don't use.
+        for probe in ["abstract-probe-armv7.o", "abstract-probe-thumbv7em.o"] {
+            let mut info = get_vtables_from_file(&fixture("abstract.o"), HOST);
+            info.address_size = 4;
+            mark_pure_virtuals(&mut info, &fixture(probe), &["concrete", "shape", "square"]);
+            let must_override = |class: &str| {
+                info.get(class)
+                    .unwrap()
+                    .iter()
+                    .filter(|entry| entry.must_override())
+                    .map(|entry| entry.name.as_str())
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(must_override("shape"), ["area", "sides"], "{}", probe);
+            assert_eq!(must_override("square"), ["sides"], "{}", probe);
+            assert!(must_override("concrete").is_empty(), "{}", probe);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "ARM objects must use the EABI")]
+    fn arm_old_abi() {
+        let mut data = fs::read(fixture("armv7.o")).unwrap();
+        data[36..40].copy_from_slice(&[0; 4]);
+        let path = std::env::temp_dir().join(format!("cpp-inherit-oabi-{}.o", std::process::id()));
+        fs::write(&path, data).unwrap();
+        let result = std::panic::catch_unwind(|| {
+            get_vtables_from_file(&path, "armv7-unknown-linux-gnueabihf")
+        });
+        fs::remove_file(&path).unwrap();
+        if let Err(panic) = result {
+            std::panic::resume_unwind(panic);
+        }
+    }
+
+    #[test]
+    fn call_triviality() {
+        let info = get_vtables_from_file(&fixture("calls.o"), HOST);
+
+        let mut non_trivial = info.non_trivial_classes.iter().collect::<Vec<_>>();
+        non_trivial.sort();
+        assert_eq!(
+            non_trivial,
+            [
+                "calls",
+                "derives",
+                "holds_array",
+                "holds_member",
+                "polymorphic",
+                "uncopyable",
+                "with_copy",
+                "with_destructor",
+            ]
+        );
+
+        let named = |name: &str| Some(CppType::Named(name.to_owned()));
+        let vtable = info.get("calls").expect("calls wasn't found");
+        let signatures = vtable
+            .iter()
+            .map(|entry| (entry.name.as_str(), &entry.return_type, &entry.params))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            signatures,
+            [
+                ("get_trivial", &named("trivial"), &vec![]),
+                ("get_with_destructor", &named("with_destructor"), &vec![]),
+                (
+                    "take",
+                    &Some(CppType::Void),
+                    &vec![
+                        named("trivial"),
+                        named("with_copy"),
+                        Some(CppType::Reference {
+                            referent: Box::new(CppType::Named("with_destructor".to_owned())),
+                            is_const: true,
+                        }),
+                        Some(CppType::Pointer {
+                            pointee: Box::new(CppType::Named("holds_member".to_owned())),
+                            is_const: false,
+                        }),
+                    ]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn base_offsets() {
+        let info = get_vtables_from_file(&fixture("covariant.o"), HOST);
+
+        assert_eq!(
+            info.base_offset("primary", "base"),
+            Some(BaseOffset::Fixed(0))
+        );
+        assert_eq!(
+            info.base_offset("secondary", "base"),
+            Some(BaseOffset::Fixed(16))
+        );
+        assert_eq!(
+            info.base_offset("nested", "base"),
+            Some(BaseOffset::Fixed(16))
+        );
+        assert_eq!(
+            info.base_offset("nested", "other"),
+            Some(BaseOffset::Fixed(0))
+        );
+        assert_eq!(
+            info.base_offset("virtual_derived", "base"),
+            Some(BaseOffset::Virtual)
+        );
+        assert_eq!(
+            info.base_offset("through_virtual", "base"),
+            Some(BaseOffset::Virtual)
+        );
+        assert_eq!(info.base_offset("other", "base"), None);
+        assert_eq!(info.base_offset("base", "base"), None);
+
+        let base = || Box::new(CppType::Named("base".to_owned()));
+        let vtable = info.get("base").expect("base wasn't found");
+        assert_eq!(
+            vtable[0].return_type,
+            Some(CppType::Pointer {
+                pointee: base(),
+                is_const: false,
+            })
+        );
+        assert_eq!(
+            vtable[1].return_type,
+            Some(CppType::Reference {
+                referent: base(),
+                is_const: true,
+            })
+        );
+    }
+
+    #[test]
+    fn parse_qualifiers() {
+        let qualifiers = "const&&".parse::<Qualifiers>().unwrap();
+        assert_eq!(
+            qualifiers,
+            Qualifiers {
+                is_const: true,
+                is_volatile: false,
+                reference: RefQualifier::RValue,
+            }
+        );
+        assert_eq!(qualifiers.to_string(), "const &&");
+        assert_eq!("".parse::<Qualifiers>(), Ok(Qualifiers::default()));
+        assert!("const const".parse::<Qualifiers>().is_err());
+        assert!("& &&".parse::<Qualifiers>().is_err());
+        assert!("mutable".parse::<Qualifiers>().is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "but the target is mipsel-unknown-linux-gnu (32-bit, little endian)")]
+    fn big_endian_object_for_little_endian_target() {
+        get_vtables_from_file(&fixture("mips.o"), "mipsel-unknown-linux-gnu");
+    }
+
+    #[test]
+    #[should_panic(expected = "but the target is x86_64-unknown-linux-gnu (64-bit, little endian)")]
+    fn arm_object_for_x86_64_target() {
+        get_vtables_from_file(&fixture("armv7.o"), "x86_64-unknown-linux-gnu");
+    }
+}
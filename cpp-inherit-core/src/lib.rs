@@ -0,0 +1,451 @@
+//! The class and vtable model behind `cpp-inherit`'s macros, read from the debug info of a
+//! compiled header (DWARF) or an MSVC PDB (CodeView).
+//!
+//! ```no_run
+//! let target = cpp_inherit_core::target_triple();
+//! let out_path = std::env::temp_dir().join("test.o");
+//! let info = cpp_inherit_core::read_vtable_info("src/test.hpp".as_ref(), &out_path, &target);
+//! for entry in info.get("base").unwrap() {
+//!     println!("{}: {} ({})", entry.pos, entry.name, entry.default);
+//! }
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+pub mod codeview;
+pub mod dwarf;
+pub mod metadata;
+pub mod temp_dir;
+pub use dwarf::{Qualifiers, RefQualifier, VTableElement, Virtuality};
+
+/// The C++ ABI a set of vtables was laid out for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CppAbi {
+    Itanium,
+    Msvc,
+}
+
+pub struct VTableInfo {
+    pub abi: CppAbi,
+    pub architecture: object::Architecture,
+    /// Size of a pointer, and so of each vtable slot, in bytes
+    pub address_size: u8,
+    pub classes: HashMap<String, Vec<VTableElement>>,
+    /// The constructors of each class which can be called from Rust
+    pub constructors: HashMap<String, Vec<Constructor>>,
+    /// Classes which are non-trivial for the purposes of calls (e.g. they have a copy constructor
+    /// or destructor), which the Itanium ABI passes and returns through a pointer to a temporary
+    pub non_trivial_classes: HashSet<String>,
+    /// The direct bases of each class
+    pub bases: HashMap<String, Vec<BaseClass>>,
+}
+
+impl VTableInfo {
+    pub fn get(&self, class: &str) -> Option<&Vec<VTableElement>> {
+        self.classes.get(class)
+    }
+
+    pub fn constructors(&self, class: &str) -> &[Constructor] {
+        self.constructors
+            .get(class)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Where `base` is in `class`, if it's one of its (direct or indirect) bases
+    pub fn base_offset(&self, class: &str, base: &str) -> Option<BaseOffset> {
+        self.bases.get(class)?.iter().find_map(|direct| {
+            let inner = if direct.name == base {
+                BaseOffset::Fixed(0)
+            } else {
+                self.base_offset(&direct.name, base)?
+            };
+            Some(match (direct.offset, inner) {
+                (Some(offset), BaseOffset::Fixed(inner)) => BaseOffset::Fixed(offset + inner),
+                _ => BaseOffset::Virtual,
+            })
+        })
+    }
+
+    /// The calling convention (as a Rust ABI string) C++ uses for member functions called
+    /// through the vtable
+    pub fn method_abi(&self, target: &str) -> &'static str {
+        // Only 32-bit x86 has a separate member function calling convention, which both MSVC and
+        // MinGW use
+        let x86_windows = target_arch(target) == "x86" && target.contains("windows");
+        match (self.abi, self.architecture) {
+            (CppAbi::Msvc, object::Architecture::I386) => "thiscall",
+            _ if x86_windows => "thiscall",
+            _ => "C",
+        }
+    }
+
+    /// Whether complete object constructors and destructors (`C1`/`D1`) return `this`, as they do
+    /// in the ARM C++ ABI, which Apple's ARM64 ABI follows, and in WebAssembly's
+    pub fn structors_return_this(&self, target: &str) -> bool {
+        self.abi == CppAbi::Itanium
+            && match target_arch(target) {
+                "arm" | "wasm32" | "wasm64" => true,
+                "aarch64" => target.contains("-apple-"),
+                _ => false,
+            }
+    }
+}
+
+/// The type of a constructor or method parameter, as far as it's needed to declare it in Rust
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CppType {
+    Void,
+    Bool,
+    /// An integer (including the character types) of `size` bytes
+    Int {
+        size: u8,
+        signed: bool,
+    },
+    Float {
+        size: u8,
+    },
+    /// A class, struct, union or enum, by its unqualified name
+    Named(String),
+    Pointer {
+        pointee: Box<CppType>,
+        is_const: bool,
+    },
+    Reference {
+        referent: Box<CppType>,
+        is_const: bool,
+    },
+}
+
+/// A constructor, taking its parameters after the `this` pointer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Constructor {
+    /// The symbol of the complete object (`C1`) constructor
+    pub symbol: String,
+    pub params: Vec<CppType>,
+}
+
+/// A class' direct base class
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BaseClass {
+    pub name: String,
+    /// Where the base is in the class, in bytes. Virtual bases have no fixed offset.
+    pub offset: Option<u64>,
+}
+
+/// Where a base class is in a class which derives from it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BaseOffset {
+    /// At a fixed offset, in bytes
+    Fixed(u64),
+    /// In a virtual base, which is only found at runtime
+    Virtual,
+}
+
+/// The target triple being compiled for.
+///
+/// Cargo only tells build scripts the target, so this reads `CPP_INHERIT_TARGET` (or `TARGET`)
+/// which a build script can forward with `cargo:rustc-env`, falling back to the host.
+pub fn target_triple() -> String {
+    env::var("CPP_INHERIT_TARGET")
+        .or_else(|_| env::var("TARGET"))
+        .unwrap_or_else(|_| env!("CPP_INHERIT_HOST").to_owned())
+}
+
+/// The `target_arch` cfg value for a target triple
+pub fn target_arch(target: &str) -> &str {
+    match target.split('-').next().unwrap_or_default() {
+        "i386" | "i586" | "i686" => "x86",
+        "aarch64_be" | "arm64" | "arm64e" | "arm64_32" => "aarch64",
+        arch if arch.starts_with("arm") || arch.starts_with("thumb") => "arm",
+        "mips64el" | "mipsisa64r6" | "mipsisa64r6el" => "mips64",
+        "mipsel" | "mipsisa32r6" | "mipsisa32r6el" => "mips",
+        "powerpc64le" => "powerpc64",
+        "sparcv9" => "sparc64",
+        arch if arch.starts_with("riscv64") => "riscv64",
+        arch if arch.starts_with("riscv32") => "riscv32",
+        arch => arch,
+    }
+}
+
+/// The `target_pointer_width` cfg value for a target triple
+pub fn target_pointer_width(target: &str) -> u8 {
+    match target_arch(target) {
+        _ if target.ends_with("gnux32")
+            || target.contains("ilp32")
+            || target.starts_with("arm64_32") =>
+        {
+            32
+        }
+        "x86_64" | "aarch64" | "powerpc64" | "mips64" | "s390x" | "sparc64" | "loongarch64"
+        | "riscv64" | "wasm64" => 64,
+        _ => 32,
+    }
+}
+
+/// The `target_endian` cfg value for a target triple
+pub fn target_endian(target: &str) -> &'static str {
+    let arch = target.split('-').next().unwrap_or_default();
+    let big = match target_arch(target) {
+        "arm" => arch.starts_with("armeb") || arch.starts_with("thumbeb"),
+        "aarch64" => arch == "aarch64_be",
+        "mips" | "mips64" => !arch.ends_with("el"),
+        "powerpc" | "s390x" | "sparc" | "sparc64" | "m68k" => true,
+        "powerpc64" => arch != "powerpc64le",
+        _ => false,
+    };
+
+    if big {
+        "big"
+    } else {
+        "little"
+    }
+}
+
+/// The GNU triple cross toolchains are usually prefixed with, e.g. `aarch64-linux-gnu`
+fn gnu_triple(target: &str) -> String {
+    let mut parts = target.split('-').collect::<Vec<_>>();
+    if parts.len() == 4 {
+        // Drop the vendor (`unknown`, `pc`, ...)
+        parts.remove(1);
+    }
+    match target_arch(target) {
+        // Bare metal toolchains are `arm-none-eabi` whatever the float ABI
+        "arm" if target.contains("-none-") => return "arm-none-eabi".to_owned(),
+        "arm" => parts[0] = "arm",
+        "x86" => parts[0] = "i686",
+        _ => {}
+    }
+    parts.join("-")
+}
+
+/// Compilers to try, in order, for compiling a header for the given target
+fn compiler_commands(target: &str) -> Vec<Command> {
+    let gcc_args = [
+        // I don't really know why some of these can't be removed but probably best to leave
+        // these be
+        "-femit-class-debug-always",
+        "-fno-eliminate-unused-debug-types",
+        "-fno-eliminate-unused-debug-symbols",
+        "-g3",
+    ];
+    let clang_args = [
+        "-fstandalone-debug",
+        "-fno-eliminate-unused-debug-types",
+        "-g3",
+    ];
+
+    let gcc = |program: &str| {
+        let mut command = Command::new(program);
+        command.args(gcc_args);
+        command
+    };
+    let mut clang = Command::new("clang++");
+    clang.arg(format!("--target={}", target)).args(clang_args);
+
+    // An explicitly configured compiler wins, like the `cc` crate
+    let configured = [
+        format!("CXX_{}", target.replace('-', "_")),
+        "CXX".to_owned(),
+    ]
+    .iter()
+    .find_map(|var| env::var(var).ok());
+    if let Some(cxx) = configured {
+        return if cxx.contains("clang") {
+            let mut command = Command::new(cxx);
+            command.arg(format!("--target={}", target)).args(clang_args);
+            vec![command]
+        } else {
+            vec![gcc(&cxx)]
+        };
+    }
+
+    if target == env!("CPP_INHERIT_HOST") {
+        vec![gcc("g++"), clang]
+    } else {
+        vec![gcc(&format!("{}-g++", gnu_triple(target))), clang]
+    }
+}
+
+/// Read the vtables from a PDB or an object file, or from a header after compiling it to
+/// `out_path`
+pub fn read_vtable_info(header_path: &Path, out_path: &Path, target: &str) -> VTableInfo {
+    read_vtable_info_with_flags(header_path, out_path, target, &[])
+}
+
+/// Like [`read_vtable_info`], passing extra flags to the compiler
+pub fn read_vtable_info_with_flags(
+    header_path: &Path,
+    out_path: &Path,
+    target: &str,
+    flags: &[String],
+) -> VTableInfo {
+    // MSVC-compiled binaries can't have their headers compiled here, so read the PDB instead
+    if header_path.extension() == Some("pdb".as_ref()) {
+        return codeview::get_vtables_from_pdb(header_path);
+    }
+
+    // Existing build outputs can be used as-is, including ones with split debug info
+    if header_path.extension() == Some("o".as_ref()) {
+        return dwarf::get_vtables_from_file(header_path, target);
+    }
+
+    // Compile the header to an unstripped object file for the target, using the first compiler
+    // which is installed
+    let (compiler, output) = compiler_commands(target)
+        .into_iter()
+        .enumerate()
+        .find_map(|(i, mut command)| {
+            command
+                .args(flags)
+                .args(["-x", "c++", "-c"])
+                .arg("-o")
+                .arg(out_path)
+                .arg(header_path)
+                .stdin(Stdio::null())
+                .output()
+                .ok()
+                .map(|output| (i, output))
+        })
+        .unwrap_or_else(|| panic!("Failed to find a C++ compiler for target {}", target));
+    if !output.status.success() {
+        panic!(
+            "C++ compiler error:\n{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    // Pure virtual methods are found by compiling a probe which includes the header, with the
+    // same compiler. It mustn't write the header's dependency file, which would then list the probe.
+    let header_path = fs::canonicalize(header_path).unwrap_or_else(|_| header_path.to_owned());
+    let probe_source = out_path.with_extension("probe.cpp");
+    let probe_path = out_path.with_extension("probe.o");
+    let probe_flags = without_dependency_flags(flags);
+    dwarf::get_vtables_from_header(out_path, &header_path, target, |source| {
+        fs::write(&probe_source, source).ok()?;
+        let status = compiler_commands(target)
+            .into_iter()
+            .nth(compiler)?
+            .args(&probe_flags)
+            .args(["-x", "c++", "-c"])
+            .arg("-o")
+            .arg(&probe_path)
+            .arg(&probe_source)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .ok()?;
+        Some(probe_path.clone()).filter(|_| status.success())
+    })
+}
+
+/// `flags` without those making the compiler write a dependency file (`-MD`, `-MF <path>`, ...)
+fn without_dependency_flags(flags: &[String]) -> Vec<String> {
+    let mut kept = Vec::new();
+    let mut flags = flags.iter();
+    while let Some(flag) = flags.next() {
+        match flag.as_str() {
+            "-M" | "-MM" | "-MD" | "-MMD" | "-MG" | "-MP" => {}
+            // These take the next argument unless it's joined on
+            "-MF" | "-MT" | "-MQ" => {
+                flags.next();
+            }
+            flag if ["-MF", "-MT", "-MQ"]
+                .iter()
+                .any(|option| flag.starts_with(option)) => {}
+            _ => kept.push(flag.clone()),
+        }
+    }
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> std::path::PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name)
+    }
+
+    #[test]
+    fn dependency_flags() {
+        let flags = [
+            "-Iinclude",
+            "-MD",
+            "-MF",
+            "out.d",
+            "-MTtarget",
+            "-DX=1",
+            "-MP",
+        ]
+        .iter()
+        .map(|flag| flag.to_string())
+        .collect::<Vec<_>>();
+        assert_eq!(without_dependency_flags(&flags), ["-Iinclude", "-DX=1"]);
+    }
+
+    #[test]
+    fn temp_dirs() {
+        let first = temp_dir::TempDir::new("cpp-inherit-test").unwrap();
+        let second = temp_dir::TempDir::new("cpp-inherit-test").unwrap();
+        assert_ne!(first.path(), second.path());
+
+        let path = first.path().to_owned();
+        fs::write(path.join("class.probe.cpp"), "").unwrap();
+        drop(first);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn target_arches() {
+        assert_eq!(target_arch("i686-pc-windows-msvc"), "x86");
+        assert_eq!(target_arch("i686-pc-windows-gnu"), "x86");
+        assert_eq!(target_arch("i586-unknown-linux-gnu"), "x86");
+        assert_eq!(target_arch("i686-unknown-linux-gnu"), "x86");
+        assert_eq!(target_arch("x86_64-unknown-linux-gnu"), "x86_64");
+        assert_eq!(target_arch("thumbv7em-none-eabihf"), "arm");
+        assert_eq!(target_arch("aarch64-apple-darwin"), "aarch64");
+    }
+
+    #[test]
+    fn method_abis() {
+        let msvc = codeview::get_vtables_from_pdb(&fixture("msvc.pdb"));
+        assert_eq!(msvc.method_abi("i686-pc-windows-msvc"), "thiscall");
+
+        // MinGW uses thiscall for methods too, unlike 32-bit x86 Linux
+        let i686 = dwarf::get_vtables_from_file(&fixture("i686.o"), "i686-pc-windows-gnu");
+        assert_eq!(i686.architecture, object::Architecture::I386);
+        assert_eq!(i686.get("base").map(Vec::len), Some(4));
+        assert_eq!(i686.method_abi("i686-pc-windows-gnu"), "thiscall");
+        let i686 = dwarf::get_vtables_from_file(&fixture("i686.o"), "i686-unknown-linux-gnu");
+        assert_eq!(i686.method_abi("i686-unknown-linux-gnu"), "C");
+
+        let x86_64 = dwarf::get_vtables_from_file(&fixture("dwarf5.o"), "x86_64-unknown-linux-gnu");
+        assert_eq!(x86_64.method_abi("x86_64-unknown-linux-gnu"), "C");
+        assert_eq!(x86_64.method_abi("x86_64-pc-windows-gnu"), "C");
+    }
+
+    #[test]
+    fn structors_returning_this() {
+        let armv7 =
+            dwarf::get_vtables_from_file(&fixture("armv7.o"), "armv7-unknown-linux-gnueabihf");
+        assert!(armv7.structors_return_this("armv7-unknown-linux-gnueabihf"));
+        let thumbv7em =
+            dwarf::get_vtables_from_file(&fixture("thumbv7em.o"), "thumbv7em-none-eabihf");
+        assert!(thumbv7em.structors_return_this("thumbv7em-none-eabihf"));
+
+        let x86_64 = dwarf::get_vtables_from_file(&fixture("dwarf5.o"), "x86_64-unknown-linux-gnu");
+        assert!(!x86_64.structors_return_this("x86_64-unknown-linux-gnu"));
+
+        // The deleting destructor is the only one in MSVC's ABI, and returns `this` everywhere
+        let msvc = codeview::get_vtables_from_pdb(&fixture("msvc.pdb"));
+        assert!(!msvc.structors_return_this("i686-pc-windows-msvc"));
+    }
+}
@@ -0,0 +1,306 @@
+//! The file `cpp-inherit-build` writes to `OUT_DIR` with the vtables of each header, which the
+//! macros read instead of compiling the headers themselves
+
+use serde_json::{json, Value};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use super::{BaseClass, Constructor, CppAbi, CppType, VTableElement, VTableInfo, Virtuality};
+
+/// Name of the metadata file within `OUT_DIR`
+pub const METADATA_FILE: &str = "cpp-inherit.json";
+
+/// The vtables read from one header for one target
+pub struct HeaderMetadata {
+    /// Canonical path of the header (or object file/PDB)
+    pub header: PathBuf,
+    pub target: String,
+    pub info: VTableInfo,
+}
+
+fn architecture_name(architecture: object::Architecture) -> &'static str {
+    match architecture {
+        object::Architecture::Aarch64 => "aarch64",
+        object::Architecture::Arm => "arm",
+        object::Architecture::I386 => "i386",
+        object::Architecture::Mips => "mips",
+        object::Architecture::Wasm32 => "wasm32",
+        object::Architecture::X86_64 => "x86_64",
+        _ => "unknown",
+    }
+}
+
+fn parse_architecture(name: &str) -> object::Architecture {
+    match name {
+        "aarch64" => object::Architecture::Aarch64,
+        "arm" => object::Architecture::Arm,
+        "i386" => object::Architecture::I386,
+        "mips" => object::Architecture::Mips,
+        "wasm32" => object::Architecture::Wasm32,
+        "x86_64" => object::Architecture::X86_64,
+        _ => object::Architecture::Unknown,
+    }
+}
+
+fn type_to_json(ty: &CppType) -> Value {
+    match ty {
+        CppType::Void => json!({ "kind": "void" }),
+        CppType::Bool => json!({ "kind": "bool" }),
+        CppType::Int { size, signed } => json!({ "kind": "int", "size": size, "signed": signed }),
+        CppType::Float { size } => json!({ "kind": "float", "size": size }),
+        CppType::Named(name) => json!({ "kind": "named", "name": name }),
+        CppType::Pointer { pointee, is_const } => {
+            json!({ "kind": "pointer", "pointee": type_to_json(pointee), "const": is_const })
+        }
+        CppType::Reference { referent, is_const } => {
+            json!({ "kind": "reference", "referent": type_to_json(referent), "const": is_const })
+        }
+    }
+}
+
+fn type_from_json(ty: &Value) -> Option<CppType> {
+    Some(match ty["kind"].as_str()? {
+        "void" => CppType::Void,
+        "bool" => CppType::Bool,
+        "int" => CppType::Int {
+            size: ty["size"].as_u64()? as u8,
+            signed: ty["signed"].as_bool()?,
+        },
+        "float" => CppType::Float {
+            size: ty["size"].as_u64()? as u8,
+        },
+        "named" => CppType::Named(ty["name"].as_str()?.to_owned()),
+        "pointer" => CppType::Pointer {
+            pointee: Box::new(type_from_json(&ty["pointee"])?),
+            is_const: ty["const"].as_bool()?,
+        },
+        "reference" => CppType::Reference {
+            referent: Box::new(type_from_json(&ty["referent"])?),
+            is_const: ty["const"].as_bool()?,
+        },
+        _ => return None,
+    })
+}
+
+/// A type which may have no `CppType` equivalent, written as `null`. `None` if it's malformed.
+fn optional_type_from_json(ty: &Value) -> Option<Option<CppType>> {
+    match ty {
+        Value::Null => Some(None),
+        ty => type_from_json(ty).map(Some),
+    }
+}
+
+fn header_to_json(header: &HeaderMetadata) -> Value {
+    let classes = header
+        .info
+        .classes
+        .iter()
+        .map(|(class, vtable)| {
+            let vtable = vtable
+                .iter()
+                .map(|entry| {
+                    json!({
+                        "name": entry.name,
+                        "default": entry.default,
+                        "pos": entry.pos,
+                        "pure_virtual": entry.virtuality == Virtuality::PureVirtual,
+                        "qualifiers": entry.qualifiers.to_string(),
+                        "return_type": entry.return_type.as_ref().map(type_to_json),
+                        "params": entry
+                            .params
+                            .iter()
+                            .map(|param| param.as_ref().map(type_to_json))
+                            .collect::<Vec<_>>(),
+                    })
+                })
+                .collect::<Vec<_>>();
+            (class.clone(), Value::Array(vtable))
+        })
+        .collect::<serde_json::Map<_, _>>();
+    let constructors = header
+        .info
+        .constructors
+        .iter()
+        .map(|(class, constructors)| {
+            let constructors = constructors
+                .iter()
+                .map(|constructor| {
+                    let params = constructor
+                        .params
+                        .iter()
+                        .map(type_to_json)
+                        .collect::<Vec<_>>();
+                    json!({ "symbol": constructor.symbol, "params": params })
+                })
+                .collect::<Vec<_>>();
+            (class.clone(), Value::Array(constructors))
+        })
+        .collect::<serde_json::Map<_, _>>();
+
+    let bases = header
+        .info
+        .bases
+        .iter()
+        .map(|(class, bases)| {
+            let bases = bases
+                .iter()
+                .map(|base| json!({ "name": base.name, "offset": base.offset }))
+                .collect::<Vec<_>>();
+            (class.clone(), Value::Array(bases))
+        })
+        .collect::<serde_json::Map<_, _>>();
+    // Sorted so the file is the same each build
+    let mut non_trivial_classes = header.info.non_trivial_classes.iter().collect::<Vec<_>>();
+    non_trivial_classes.sort();
+
+    json!({
+        "header": header.header,
+        "target": header.target,
+        "abi": match header.info.abi {
+            CppAbi::Itanium => "itanium",
+            CppAbi::Msvc => "msvc",
+        },
+        "architecture": architecture_name(header.info.architecture),
+        "address_size": header.info.address_size,
+        "classes": classes,
+        "constructors": constructors,
+        "non_trivial_classes": non_trivial_classes,
+        "bases": bases,
+    })
+}
+
+fn header_from_json(header: &Value) -> Option<HeaderMetadata> {
+    let classes = header["classes"]
+        .as_object()?
+        .iter()
+        .map(|(class, vtable)| {
+            let vtable = vtable
+                .as_array()?
+                .iter()
+                .map(|entry| {
+                    Some(VTableElement {
+                        name: entry["name"].as_str()?.to_owned(),
+                        default: entry["default"].as_str()?.to_owned(),
+                        pos: entry["pos"].as_u64()?,
+                        virtuality: if entry["pure_virtual"].as_bool()? {
+                            Virtuality::PureVirtual
+                        } else {
+                            Virtuality::Virtual
+                        },
+                        qualifiers: entry["qualifiers"].as_str()?.parse().ok()?,
+                        return_type: optional_type_from_json(&entry["return_type"])?,
+                        params: entry["params"]
+                            .as_array()?
+                            .iter()
+                            .map(optional_type_from_json)
+                            .collect::<Option<_>>()?,
+                    })
+                })
+                .collect::<Option<Vec<_>>>()?;
+            Some((class.clone(), vtable))
+        })
+        .collect::<Option<_>>()?;
+    let constructors = header["constructors"]
+        .as_object()?
+        .iter()
+        .map(|(class, constructors)| {
+            let constructors = constructors
+                .as_array()?
+                .iter()
+                .map(|constructor| {
+                    Some(Constructor {
+                        symbol: constructor["symbol"].as_str()?.to_owned(),
+                        params: constructor["params"]
+                            .as_array()?
+                            .iter()
+                            .map(type_from_json)
+                            .collect::<Option<_>>()?,
+                    })
+                })
+                .collect::<Option<Vec<_>>>()?;
+            Some((class.clone(), constructors))
+        })
+        .collect::<Option<_>>()?;
+
+    let bases = header["bases"]
+        .as_object()?
+        .iter()
+        .map(|(class, bases)| {
+            let bases = bases
+                .as_array()?
+                .iter()
+                .map(|base| {
+                    Some(BaseClass {
+                        name: base["name"].as_str()?.to_owned(),
+                        offset: match &base["offset"] {
+                            Value::Null => None,
+                            offset => Some(offset.as_u64()?),
+                        },
+                    })
+                })
+                .collect::<Option<Vec<_>>>()?;
+            Some((class.clone(), bases))
+        })
+        .collect::<Option<_>>()?;
+
+    Some(HeaderMetadata {
+        header: header["header"].as_str()?.into(),
+        target: header["target"].as_str()?.to_owned(),
+        info: VTableInfo {
+            abi: match header["abi"].as_str()? {
+                "msvc" => CppAbi::Msvc,
+                _ => CppAbi::Itanium,
+            },
+            architecture: parse_architecture(header["architecture"].as_str()?),
+            address_size: header["address_size"].as_u64()? as u8,
+            classes,
+            constructors,
+            non_trivial_classes: header["non_trivial_classes"]
+                .as_array()?
+                .iter()
+                .map(|class| class.as_str().map(str::to_owned))
+                .collect::<Option<_>>()?,
+            bases,
+        },
+    })
+}
+
+/// Write the metadata file into `out_dir`
+pub fn write(out_dir: &Path, headers: &[HeaderMetadata]) {
+    let headers = headers.iter().map(header_to_json).collect::<Vec<_>>();
+    let json = serde_json::to_string_pretty(&json!({ "headers": headers })).unwrap();
+    fs::write(out_dir.join(METADATA_FILE), json).unwrap_or_else(|error| {
+        panic!(
+            "Failed to write {}: {}",
+            out_dir.join(METADATA_FILE).display(),
+            error
+        )
+    });
+}
+
+/// Read the metadata file in `out_dir`, if a build script wrote one
+pub fn read(out_dir: &Path) -> Option<Vec<HeaderMetadata>> {
+    let path = out_dir.join(METADATA_FILE);
+    let json = fs::read_to_string(&path).ok()?;
+    let json = serde_json::from_str::<Value>(&json)
+        .unwrap_or_else(|error| panic!("Failed to parse {}: {}", path.display(), error));
+
+    let headers = json["headers"]
+        .as_array()
+        .and_then(|headers| headers.iter().map(header_from_json).collect());
+    match headers {
+        Some(headers) => Some(headers),
+        None => panic!("{} is not a cpp-inherit metadata file", path.display()),
+    }
+}
+
+/// The precomputed vtables of a header, if the metadata file in `out_dir` has them for the target
+pub fn find(out_dir: &Path, header: &Path, target: &str) -> Option<VTableInfo> {
+    let header = header.canonicalize().ok()?;
+    read(out_dir)?
+        .into_iter()
+        .find(|metadata| metadata.header == header && metadata.target == target)
+        .map(|metadata| metadata.info)
+}
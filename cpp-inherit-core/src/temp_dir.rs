@@ -0,0 +1,44 @@
+//! A private temporary directory to compile headers in, for when there's no `OUT_DIR`
+
+use std::collections::hash_map::RandomState;
+use std::env;
+use std::fs;
+use std::hash::BuildHasher;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::SystemTime;
+
+/// A newly created directory in the system's temp dir, which is removed with everything in it
+/// when dropped
+#[derive(Debug)]
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    /// Create a directory named `<prefix>-<pid>-<random>`. Creating it fails if the name is
+    /// taken, so nothing else can have put files (or a symlink) there first.
+    pub fn new(prefix: &str) -> io::Result<Self> {
+        loop {
+            let random = RandomState::new().hash_one(SystemTime::now());
+            let name = format!("{}-{}-{:016x}", prefix, process::id(), random);
+            let path = env::temp_dir().join(name);
+            match fs::create_dir(&path) {
+                Ok(()) => return Ok(Self { path }),
+                Err(error) if error.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(error) => return Err(error),
+            }
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
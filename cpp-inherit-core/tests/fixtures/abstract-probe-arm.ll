@@ -0,0 +1,85 @@
+; The vtables and typeinfo clang emits for abstract-probe.cpp on ARM, with ARM's `this`-returning
+; destructors, for llc to compile. There's no ARM C++ compiler here, but this is at least real ARM
+; (and Thumb) code generation and relocations, unlike the objects reassembled from x86 output.
+
+%si_class_type_info = type { ptr, ptr, ptr }
+
+@_ZTVN10__cxxabiv117__class_type_infoE = external global ptr
+@_ZTVN10__cxxabiv120__si_class_type_infoE = external global ptr
+@_ZTI8concrete = external constant ptr
+@_ZTIN6shapes5shapeE = external constant ptr
+@_ZTI6square = external constant ptr
+
+@_ZTS19cpp_inherit_probe_0 = constant [22 x i8] c"19cpp_inherit_probe_0\00"
+@_ZTS19cpp_inherit_probe_1 = constant [22 x i8] c"19cpp_inherit_probe_1\00"
+@_ZTS19cpp_inherit_probe_2 = constant [22 x i8] c"19cpp_inherit_probe_2\00"
+
+@_ZTI19cpp_inherit_probe_0 = constant %si_class_type_info {
+  ptr getelementptr (ptr, ptr @_ZTVN10__cxxabiv120__si_class_type_infoE, i32 2),
+  ptr @_ZTS19cpp_inherit_probe_0, ptr @_ZTI8concrete }
+@_ZTI19cpp_inherit_probe_1 = constant %si_class_type_info {
+  ptr getelementptr (ptr, ptr @_ZTVN10__cxxabiv120__si_class_type_infoE, i32 2),
+  ptr @_ZTS19cpp_inherit_probe_1, ptr @_ZTIN6shapes5shapeE }
+@_ZTI19cpp_inherit_probe_2 = constant %si_class_type_info {
+  ptr getelementptr (ptr, ptr @_ZTVN10__cxxabiv120__si_class_type_infoE, i32 2),
+  ptr @_ZTS19cpp_inherit_probe_2, ptr @_ZTI6square }
+
+@_ZTV19cpp_inherit_probe_0 = constant { [4 x ptr] } { [4 x ptr] [
+  ptr null, ptr @_ZTI19cpp_inherit_probe_0,
+  ptr @_ZN8concrete1xEv,
+  ptr @_ZN19cpp_inherit_probe_017cpp_inherit_probeEv ] }
+@_ZTV19cpp_inherit_probe_1 = constant { [8 x ptr] } { [8 x ptr] [
+  ptr null, ptr @_ZTI19cpp_inherit_probe_1,
+  ptr @_ZN19cpp_inherit_probe_1D1Ev, ptr @_ZN19cpp_inherit_probe_1D0Ev,
+  ptr @__cxa_pure_virtual,
+  ptr @_ZNK6shapes5shape4nameEv,
+  ptr @__cxa_pure_virtual,
+  ptr @_ZN19cpp_inherit_probe_117cpp_inherit_probeEv ] }
+@_ZTV19cpp_inherit_probe_2 = constant { [8 x ptr] } { [8 x ptr] [
+  ptr null, ptr @_ZTI19cpp_inherit_probe_2,
+  ptr @_ZN19cpp_inherit_probe_2D1Ev, ptr @_ZN19cpp_inherit_probe_2D0Ev,
+  ptr @_ZNK6square4areaEv,
+  ptr @_ZNK6shapes5shape4nameEv,
+  ptr @__cxa_pure_virtual,
+  ptr @_ZN19cpp_inherit_probe_217cpp_inherit_probeEv ] }
+
+declare void @__cxa_pure_virtual()
+declare i32 @_ZN8concrete1xEv(ptr)
+declare ptr @_ZNK6shapes5shape4nameEv(ptr)
+declare double @_ZNK6square4areaEv(ptr)
+declare ptr @_ZN6shapes5shapeD2Ev(ptr returned)
+declare void @_ZdlPv(ptr)
+
+define void @_ZN19cpp_inherit_probe_017cpp_inherit_probeEv(ptr %this) {
+  ret void
+}
+
+define void @_ZN19cpp_inherit_probe_117cpp_inherit_probeEv(ptr %this) {
+  ret void
+}
+
+define void @_ZN19cpp_inherit_probe_217cpp_inherit_probeEv(ptr %this) {
+  ret void
+}
+
+define linkonce_odr ptr @_ZN19cpp_inherit_probe_1D1Ev(ptr returned %this) {
+  %1 = call ptr @_ZN6shapes5shapeD2Ev(ptr %this)
+  ret ptr %this
+}
+
+define linkonce_odr void @_ZN19cpp_inherit_probe_1D0Ev(ptr %this) {
+  %1 = call ptr @_ZN19cpp_inherit_probe_1D1Ev(ptr %this)
+  call void @_ZdlPv(ptr %this)
+  ret void
+}
+
+define linkonce_odr ptr @_ZN19cpp_inherit_probe_2D1Ev(ptr returned %this) {
+  %1 = call ptr @_ZN6shapes5shapeD2Ev(ptr %this)
+  ret ptr %this
+}
+
+define linkonce_odr void @_ZN19cpp_inherit_probe_2D0Ev(ptr %this) {
+  %1 = call ptr @_ZN19cpp_inherit_probe_2D1Ev(ptr %this)
+  call void @_ZdlPv(ptr %this)
+  ret void
+}
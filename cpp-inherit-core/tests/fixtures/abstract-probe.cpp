@@ -0,0 +1,7 @@
+#include "abstract.hpp"
+struct cpp_inherit_probe_0 : concrete { virtual void cpp_inherit_probe(); };
+void cpp_inherit_probe_0::cpp_inherit_probe() {}
+struct cpp_inherit_probe_1 : shapes::shape { virtual void cpp_inherit_probe(); };
+void cpp_inherit_probe_1::cpp_inherit_probe() {}
+struct cpp_inherit_probe_2 : square { virtual void cpp_inherit_probe(); };
+void cpp_inherit_probe_2::cpp_inherit_probe() {}
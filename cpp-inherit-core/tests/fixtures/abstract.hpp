@@ -0,0 +1,17 @@
+namespace shapes {
+struct shape {
+    virtual ~shape();
+    virtual double area() const = 0;
+    virtual const char* name() const;
+    virtual int sides() const = 0;
+};
+}
+
+// Still abstract, as `sides` isn't overridden
+struct square : shapes::shape {
+    double area() const override;
+};
+
+struct concrete {
+    virtual int x();
+};
@@ -0,0 +1,97 @@
+#!/usr/bin/env python3
+"""Adds a symbol records stream of S_PUB32 records to a PDB from `llvm-pdbutil yaml2pdb`, which
+doesn't write one. Each line of stdin is a public symbol's `segment offset name`.
+
+The MSF container is rewritten with the new stream appended, and the DBI stream's header pointed
+at it."""
+import struct
+import sys
+
+MAGIC = b"Microsoft C/C++ MSF 7.00\r\n\x1aDS\0\0\0"
+S_PUB32 = 0x110E
+NIL_STREAM = 0xFFFFFFFF
+DBI_STREAM = 3
+# Offset of `SymRecordStream` in the DBI stream's header
+DBI_SYM_RECORD_STREAM = 20
+
+
+def read_msf(data):
+    block_size, _, _, directory_size, _, block_map = struct.unpack_from("<6I", data, len(MAGIC))
+    block = lambda index: data[index * block_size : (index + 1) * block_size]
+    blocks = lambda count: (count + block_size - 1) // block_size
+
+    directory_blocks = struct.unpack_from("<%dI" % blocks(directory_size), block(block_map))
+    directory = b"".join(block(index) for index in directory_blocks)[:directory_size]
+
+    (count,) = struct.unpack_from("<I", directory)
+    sizes = struct.unpack_from("<%dI" % count, directory, 4)
+    position = 4 + 4 * count
+    streams = []
+    for size in sizes:
+        used = 0 if size == NIL_STREAM else size
+        indices = struct.unpack_from("<%dI" % blocks(used), directory, position)
+        position += 4 * len(indices)
+        streams.append(b"".join(block(index) for index in indices)[:used])
+    return block_size, streams
+
+
+def write_msf(block_size, streams):
+    blocks = lambda count: (count + block_size - 1) // block_size
+    pad = lambda data: data + b"\0" * (blocks(len(data)) * block_size - len(data))
+
+    # The superblock and both free block maps come first
+    next_block = 3
+    contents = []
+    directory = [struct.pack("<I", len(streams))]
+    directory += [struct.pack("<I", len(stream)) for stream in streams]
+    for stream in streams:
+        indices = range(next_block, next_block + blocks(len(stream)))
+        directory.append(struct.pack("<%dI" % len(indices), *indices))
+        contents.append(pad(stream))
+        next_block += len(indices)
+    directory = b"".join(directory)
+
+    directory_blocks = range(next_block, next_block + blocks(len(directory)))
+    contents.append(pad(directory))
+    block_map = next_block + len(directory_blocks)
+    contents.append(pad(struct.pack("<%dI" % len(directory_blocks), *directory_blocks)))
+    block_count = block_map + 1
+
+    superblock = MAGIC + struct.pack("<6I", block_size, 1, block_count, len(directory), 0, block_map)
+    # Every block is in use, set bits are free ones
+    free_block_map = bytearray(b"\xff" * block_size)
+    for index in range(block_count):
+        free_block_map[index // 8] &= ~(1 << (index % 8))
+    return pad(superblock) + bytes(free_block_map) + b"\xff" * block_size + b"".join(contents)
+
+
+def public_record(segment, offset, name):
+    # Flags 2 is `cvpsfFunction`
+    record = struct.pack("<HIIH", S_PUB32, 2, offset, segment) + name.encode() + b"\0"
+    record += b"\0" * (-(len(record) + 2) % 4)
+    return struct.pack("<H", len(record)) + record
+
+
+def main():
+    path = sys.argv[1]
+    with open(path, "rb") as file:
+        data = file.read()
+    assert data.startswith(MAGIC)
+    block_size, streams = read_msf(data)
+
+    records = b""
+    for line in sys.stdin:
+        if line.strip():
+            segment, offset, name = line.split()
+            records += public_record(int(segment, 0), int(offset, 0), name)
+
+    dbi = bytearray(streams[DBI_STREAM])
+    struct.pack_into("<H", dbi, DBI_SYM_RECORD_STREAM, len(streams))
+    streams[DBI_STREAM] = bytes(dbi)
+    streams.append(records)
+
+    with open(path, "wb") as file:
+        file.write(write_msf(block_size, streams))
+
+
+main()
@@ -0,0 +1,99 @@
+#!/bin/sh
+# Builds the DWARF and PDB readers' fixtures without needing a cross compiler: g++ emits the debug
+# info for a 32 or 64-bit target as assembly, which llvm-mc then assembles for each target.
+#
+# The ARM, PowerPC and MIPS objects are x86 g++ output reassembled, not a cross compiler's, so
+# they only test reading those objects (relocations, endianness, pointer width). Where ARM's C++
+# ABI differs, in `C1`/`D1` returning `this`, that's decided from the target triple instead, and
+# objects from before the ARM EABI are rejected. The key function rules don't matter, as every
+# vtable read is the probe's, whose key function is defined in the probe.
+set -e
+cd "$(dirname "$0")"
+
+FLAGS="-femit-class-debug-always -fno-eliminate-unused-debug-types \
+    -fno-eliminate-unused-debug-symbols -g -gdwarf-4 -x c++ -S"
+g++ -m32 $FLAGS -o vtables32.s vtables.hpp
+g++ -m64 $FLAGS -o vtables64.s vtables.hpp
+
+# `@` starts a comment in ARM assembly
+sed 's/@progbits/%progbits/' vtables32.s > vtables32-arm.s
+
+llvm-mc -triple=armv7-unknown-linux-gnueabihf -filetype=obj -o armv7.o vtables32-arm.s
+llvm-mc -triple=thumbv7em-none-eabihf -filetype=obj -o thumbv7em.o vtables32-arm.s
+llvm-mc -triple=armebv7r-none-eabi -filetype=obj -o armebv7r.o vtables32-arm.s
+llvm-mc -triple=powerpc-unknown-linux-gnu -filetype=obj -o powerpc.o vtables32.s
+llvm-mc -triple=powerpc64-unknown-linux-gnu -filetype=obj -o powerpc64.o vtables64.s
+llvm-mc -triple=mips-unknown-linux-gnu -filetype=obj -o mips.o vtables32.s
+
+rm vtables32.s vtables64.s vtables32-arm.s
+
+# RISC-V objects need some code in them for the assembler to emit ADD/SUB relocation pairs for
+# the lengths in the debug info, so riscv.cpp defines a trivial function which is translated
+RISCV_FLAGS="$FLAGS -O2 -gno-variable-location-views"
+g++ -m32 $RISCV_FLAGS -o riscv32.s riscv.cpp
+g++ -m64 $RISCV_FLAGS -o riscv64.s riscv.cpp
+sed -i 's/movl\t\$1, %eax/li a0, 1/' riscv32.s riscv64.s
+llvm-mc -triple=riscv32-unknown-linux-gnu -mattr=+relax -filetype=obj -o riscv32.o riscv32.s
+llvm-mc -triple=riscv64-unknown-linux-gnu -mattr=+relax -filetype=obj -o riscv64.o riscv64.s
+rm riscv32.s riscv64.s
+
+# Native objects for the DWARF 5, split DWARF and compressed section forms. The prefix map
+# makes DW_AT_comp_dir relative so the `.dwo` files are found next to the objects.
+FLAGS="$FLAGS -fdebug-prefix-map=$PWD=."
+FLAGS=$(echo "$FLAGS" | sed 's/-gdwarf-4 -x c++ -S/-x c++ -c/')
+g++ $FLAGS -gdwarf-5 -o dwarf5.o vtables.hpp
+g++ $FLAGS -gdwarf-5 -gsplit-dwarf -o split-dwarf5.o vtables.hpp
+g++ $FLAGS -gdwarf-4 -gsplit-dwarf -o split-gnu.o vtables.hpp
+g++ $FLAGS -gdwarf-5 -gsplit-dwarf -o split-package.o vtables.hpp
+llvm-dwp -e split-package.o -o split-package.dwp
+rm split-package.dwo
+g++ $FLAGS -gdwarf-4 -fdebug-types-section -o types-dwarf4.o vtables.hpp
+g++ $FLAGS -gdwarf-5 -fdebug-types-section -o types-dwarf5.o vtables.hpp
+g++ $FLAGS -gdwarf-5 -fdebug-types-section -gsplit-dwarf -o types-split.o vtables.hpp
+llvm-objcopy --compress-debug-sections=zlib dwarf5.o compressed.o
+llvm-objcopy --compress-debug-sections=zlib-gnu dwarf5.o compressed-gnu.o
+
+# 32-bit x86, whose methods use a different calling convention on Windows
+g++ -m32 $FLAGS -gdwarf-5 -o i686.o vtables.hpp
+
+# Constructors, also with the parameter types in type units
+g++ $FLAGS -gdwarf-5 -o constructors.o constructors.hpp
+g++ $FLAGS -gdwarf-5 -fdebug-types-section -o constructors-types.o constructors.hpp
+
+# Methods which only differ by their cv and ref qualifiers
+g++ $FLAGS -gdwarf-5 -o qualifiers.o qualifiers.hpp
+
+# Classes which are and aren't trivial for the purposes of calls, passed to virtual methods
+g++ $FLAGS -gdwarf-5 -o calls.o calls.hpp
+
+# Classes deriving from a base at different offsets, for covariant return types
+g++ $FLAGS -gdwarf-5 -o covariant.o covariant.hpp
+
+# Abstract classes, which g++ doesn't mark pure virtual methods of in the debug info, and the
+# probe deriving from each of them which `dwarf::pure_virtual_probe` generates for the header
+g++ $FLAGS -gdwarf-5 -o abstract.o abstract.hpp
+g++ $FLAGS -gdwarf-5 -o abstract-probe.o abstract-probe.cpp
+
+# The same probe's vtables for ARM and Thumb, from LLVM IR written like clang's output as there's
+# no ARM C++ compiler here, so the vtables at least have real ARM relocations and Thumb symbols
+llc -opaque-pointers -mtriple=armv7-unknown-linux-gnueabihf -filetype=obj \
+    -o abstract-probe-armv7.o abstract-probe-arm.ll
+llc -opaque-pointers -mtriple=thumbv7em-none-eabihf -filetype=obj \
+    -o abstract-probe-thumbv7em.o abstract-probe-arm.ll
+
+# An MSVC PDB, written from YAML as there's no MSVC here. yaml2pdb doesn't write public symbols,
+# so they're added afterwards, at the addresses of the procedures in msvc.yaml.
+llvm-pdbutil yaml2pdb -pdb=msvc.pdb msvc.yaml
+python3 add_publics.py msvc.pdb <<SYMBOLS
+1 0x10 ?x@base@@UAEHXZ
+1 0x20 ?x@base@@UAEHH@Z
+1 0x30 ?x@base@@UBEHXZ
+1 0x40 ?other@base@@UAEHXZ
+1 0x50 ??_Gbase@@UAEPAXI@Z
+1 0x60 ??_Ebase@@UAEPAXI@Z
+1 0x70 ?x@derived@@UAEHH@Z
+1 0x80 ?pure@derived@@UAEHXZ
+1 0x90 ?y@derived@@UAEHXZ
+1 0xa0 ??_Gderived@@UAEPAXI@Z
+1 0xb0 ?z@base@@UAEHXZ
+SYMBOLS
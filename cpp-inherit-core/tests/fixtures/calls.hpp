@@ -0,0 +1,50 @@
+struct trivial {
+    int x, y, z;
+};
+
+struct defaulted {
+    defaulted(const defaulted&) = default;
+    ~defaulted() = default;
+    int x;
+};
+
+struct move_only {
+    move_only(move_only&&) = default;
+    int x;
+};
+
+struct with_destructor {
+    ~with_destructor();
+    int x;
+};
+
+struct with_copy {
+    with_copy(const with_copy&);
+    int x;
+};
+
+struct uncopyable {
+    uncopyable(const uncopyable&) = delete;
+    uncopyable(uncopyable&&) = delete;
+    int x;
+};
+
+struct polymorphic {
+    virtual void f();
+};
+
+struct holds_member {
+    with_destructor member;
+};
+
+struct holds_array {
+    with_copy members[2];
+};
+
+struct derives : with_copy {};
+
+struct calls {
+    virtual trivial get_trivial();
+    virtual with_destructor get_with_destructor() const;
+    virtual void take(trivial a, with_copy b, const with_destructor& c, holds_member* d);
+};
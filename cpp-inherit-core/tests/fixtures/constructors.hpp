@@ -0,0 +1,22 @@
+#include <stddef.h>
+
+struct manager;
+enum class kind { a, b };
+typedef unsigned int flags;
+
+struct widget {
+    virtual ~widget();
+    widget();
+    widget(manager& owner, const char* name, flags options);
+    widget(const widget* parent, kind kind, double scale, bool visible, size_t size);
+    widget(const widget&) = delete;
+    widget(int, ...);
+    widget(void (*callback)());
+};
+
+template <typename T>
+struct holder : widget {
+    holder(T value);
+};
+
+template struct holder<short>;
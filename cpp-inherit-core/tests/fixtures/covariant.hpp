@@ -0,0 +1,24 @@
+struct base {
+    virtual base* clone() const;
+    virtual const base& self() const;
+    int b;
+};
+
+struct other {
+    virtual void f();
+    int o;
+};
+
+struct primary : base {
+    int p;
+};
+
+struct secondary : other, base {
+    int s;
+};
+
+struct nested : secondary {};
+
+struct virtual_derived : virtual base {};
+
+struct through_virtual : virtual_derived {};
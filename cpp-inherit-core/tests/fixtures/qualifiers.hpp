@@ -0,0 +1,8 @@
+struct qualified {
+    virtual int x();
+    virtual int x() const;
+    virtual int y() volatile;
+    virtual int y() const volatile;
+    virtual int z() &;
+    virtual int z() const &&;
+};
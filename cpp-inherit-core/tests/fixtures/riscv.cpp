@@ -0,0 +1,2 @@
+#include "vtables.hpp"
+int base::x() { return 1; }
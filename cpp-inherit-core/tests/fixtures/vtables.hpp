@@ -0,0 +1,11 @@
+struct base {
+    int value;
+    virtual int x();
+    virtual ~base();
+    virtual int y(int);
+};
+
+struct derived : public base {
+    virtual int y(int);
+    virtual int z();
+};
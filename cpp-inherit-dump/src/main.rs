@@ -0,0 +1,194 @@
+//! Prints the vtables cpp-inherit reads from a header, object file or PDB, for debugging macro
+//! expansions and comparing classes between SDK versions.
+
+use std::env;
+use std::path::PathBuf;
+use std::process;
+
+use cpp_inherit_core::temp_dir::TempDir;
+use cpp_inherit_core::{CppAbi, VTableElement, VTableInfo, Virtuality};
+
+const USAGE: &str = "\
+usage: cpp-inherit-dump [options] <header | object file | pdb>
+
+options:
+    --json              print JSON instead of text
+    --class <name>      only print the given class, can be repeated
+    --target <triple>   the target to compile headers for, defaults to the host";
+
+struct Options {
+    json: bool,
+    classes: Vec<String>,
+    target: String,
+    path: PathBuf,
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut json = false;
+    let mut classes = Vec::new();
+    let mut target = None;
+    let mut path = None;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--class" => classes.push(args.next().ok_or("--class requires a class name")?),
+            "--target" => target = Some(args.next().ok_or("--target requires a target triple")?),
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            _ if arg.starts_with('-') => return Err(format!("unknown option `{}`", arg)),
+            _ if path.is_none() => path = Some(PathBuf::from(arg)),
+            _ => return Err(format!("unexpected argument `{}`", arg)),
+        }
+    }
+
+    Ok(Options {
+        json,
+        classes,
+        target: target.unwrap_or_else(cpp_inherit_core::target_triple),
+        path: path.ok_or("no header, object file or pdb given")?,
+    })
+}
+
+fn abi_name(abi: CppAbi) -> &'static str {
+    match abi {
+        CppAbi::Itanium => "itanium",
+        CppAbi::Msvc => "msvc",
+    }
+}
+
+fn virtuality_name(virtuality: Virtuality) -> &'static str {
+    match virtuality {
+        Virtuality::Virtual => "virtual",
+        Virtuality::PureVirtual => "pure virtual",
+    }
+}
+
+/// The method's name followed by its qualifiers, so overloads can be told apart
+fn qualified_name(entry: &VTableElement) -> String {
+    let qualifiers = entry.qualifiers.to_string();
+    if qualifiers.is_empty() {
+        entry.name.clone()
+    } else {
+        format!("{} {}", entry.name, qualifiers)
+    }
+}
+
+fn print_text(info: &VTableInfo, classes: &[(&String, &Vec<VTableElement>)]) {
+    println!(
+        "abi: {}, architecture: {:?}, pointer size: {}",
+        abi_name(info.abi),
+        info.architecture,
+        info.address_size
+    );
+
+    for (class, vtable) in classes {
+        println!("\nclass {}", class);
+        if vtable.is_empty() {
+            println!("  (no virtual methods)");
+            continue;
+        }
+
+        let name_width = vtable
+            .iter()
+            .map(|entry| qualified_name(entry).len())
+            .max()
+            .unwrap_or(0);
+        println!(
+            "  {:>4}  {:>6}  {:<12}  {:<name_width$}  default",
+            "slot",
+            "offset",
+            "virtuality",
+            "name",
+            name_width = name_width
+        );
+        for entry in vtable.iter() {
+            println!(
+                "  {:>4}  {:>6}  {:<12}  {:<name_width$}  {}",
+                entry.pos,
+                format!("{:#x}", entry.pos * info.address_size as u64),
+                virtuality_name(entry.virtuality),
+                qualified_name(entry),
+                entry.default,
+                name_width = name_width
+            );
+        }
+    }
+}
+
+fn print_json(info: &VTableInfo, classes: &[(&String, &Vec<VTableElement>)]) {
+    let classes = classes
+        .iter()
+        .map(|(class, vtable)| {
+            let slots = vtable
+                .iter()
+                .map(|entry| {
+                    serde_json::json!({
+                        "slot": entry.pos,
+                        "offset": entry.pos * info.address_size as u64,
+                        "name": entry.name,
+                        "default": entry.default,
+                        "virtuality": virtuality_name(entry.virtuality),
+                        "qualifiers": entry.qualifiers.to_string(),
+                    })
+                })
+                .collect::<Vec<_>>();
+            serde_json::json!({ "name": class, "slots": slots })
+        })
+        .collect::<Vec<_>>();
+
+    let json = serde_json::json!({
+        "abi": abi_name(info.abi),
+        "architecture": format!("{:?}", info.architecture),
+        "address_size": info.address_size,
+        "classes": classes,
+    });
+    println!("{}", serde_json::to_string_pretty(&json).unwrap());
+}
+
+fn main() {
+    let options = parse_args().unwrap_or_else(|error| {
+        eprintln!("error: {}\n\n{}", error, USAGE);
+        process::exit(2);
+    });
+
+    // Headers are compiled in a private temp dir, along with the probe for pure virtual methods
+    let temp_dir = TempDir::new("cpp-inherit-dump").unwrap_or_else(|error| {
+        eprintln!("error: failed to create a temp dir: {}", error);
+        process::exit(1);
+    });
+    let out_path = temp_dir.path().join("header.o");
+    let info = cpp_inherit_core::read_vtable_info(&options.path, &out_path, &options.target);
+    drop(temp_dir);
+
+    // Sorted so the output can be diffed
+    let mut classes = if options.classes.is_empty() {
+        info.classes
+            .iter()
+            .filter(|(_, vtable)| !vtable.is_empty())
+            .collect::<Vec<_>>()
+    } else {
+        let mut classes = Vec::new();
+        for class in &options.classes {
+            match info.classes.get_key_value(class) {
+                Some(found) => classes.push(found),
+                None => {
+                    eprintln!("error: class `{}` not found", class);
+                    process::exit(1);
+                }
+            }
+        }
+        classes
+    };
+    classes.sort_by_key(|(class, _)| class.as_str());
+    classes.dedup_by_key(|(class, _)| class.as_str());
+
+    if options.json {
+        print_json(&info, &classes);
+    } else {
+        print_text(&info, &classes);
+    }
+}
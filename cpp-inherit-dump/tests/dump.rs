@@ -0,0 +1,68 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+fn abstract_hpp() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("../cpp-inherit-core/tests/fixtures/abstract.hpp")
+}
+
+/// Run the binary, checking that it leaves nothing behind in the temp dir
+fn dump(args: &[&str]) -> String {
+    let child = Command::new(env!("CARGO_BIN_EXE_cpp-inherit-dump"))
+        .args(args)
+        .arg(abstract_hpp())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let prefix = format!("cpp-inherit-dump-{}", child.id());
+    let Output { status, stdout, .. } = child.wait_with_output().unwrap();
+    assert!(status.success());
+
+    let left_behind = fs::read_dir(env::temp_dir())
+        .unwrap()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with(&prefix))
+        .count();
+    assert_eq!(left_behind, 0);
+
+    String::from_utf8(stdout).unwrap()
+}
+
+#[test]
+fn text_layout() {
+    let output = dump(&["--class", "shape"]);
+    assert!(output.starts_with("abi: itanium, "), "{}", output);
+    let shape = [
+        "class shape",
+        "  slot  offset  virtuality    name         default",
+        "     0     0x0  virtual       ~shape       _ZN6shapes5shapeD1Ev",
+        "     1     0x8  virtual       ~shape       _ZN6shapes5shapeD0Ev",
+        "     2    0x10  pure virtual  area const   _ZNK6shapes5shape4areaEv",
+        "     3    0x18  virtual       name const   _ZNK6shapes5shape4nameEv",
+        "     4    0x20  pure virtual  sides const  _ZNK6shapes5shape5sidesEv",
+    ];
+    assert_eq!(output.lines().skip(2).collect::<Vec<_>>(), shape);
+}
+
+#[test]
+fn json_layout() {
+    let output = dump(&["--json", "--class", "square", "--class", "concrete"]);
+    let json: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(json["abi"], "itanium");
+
+    let classes = json["classes"].as_array().unwrap();
+    let names = classes
+        .iter()
+        .map(|class| &class["name"])
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["concrete", "square"]);
+
+    let sides = &classes[1]["slots"][4];
+    assert_eq!(sides["name"], "sides");
+    assert_eq!(sides["qualifiers"], "const");
+    assert_eq!(sides["virtuality"], "pure virtual");
+    assert_eq!(sides["default"], "_ZNK6shapes5shape5sidesEv");
+    assert_eq!(sides["offset"], 4 * json["address_size"].as_u64().unwrap());
+}
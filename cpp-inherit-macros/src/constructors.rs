@@ -0,0 +1,112 @@
+use std::collections::HashSet;
+
+use cpp_inherit_core::{Constructor, CppType};
+use quote::{format_ident, quote, ToTokens};
+use syn::{parse_quote, Abi, Ident, Type};
+
+use crate::vtable::get_binding_symbol;
+
+/// The Rust equivalent of a C++ type, if there is one
+pub fn rust_type(ty: &CppType) -> Option<Type> {
+    Some(match ty {
+        CppType::Void => parse_quote!(::core::ffi::c_void),
+        CppType::Bool => parse_quote!(bool),
+        CppType::Int { size, signed } => {
+            let ty = format_ident!(
+                "{}{}",
+                if *signed { "i" } else { "u" },
+                u32::from(*size) * 8
+            );
+            match size {
+                1 | 2 | 4 | 8 | 16 => parse_quote!(#ty),
+                _ => return None,
+            }
+        }
+        CppType::Float { size: 4 } => parse_quote!(f32),
+        CppType::Float { size: 8 } => parse_quote!(f64),
+        CppType::Float { .. } => return None,
+        CppType::Named(name) => {
+            let name = syn::parse_str::<Ident>(name).ok()?;
+            parse_quote!(#name)
+        }
+        CppType::Pointer { pointee, is_const } => {
+            let pointee = rust_type(pointee)?;
+            if *is_const {
+                parse_quote!(*const #pointee)
+            } else {
+                parse_quote!(*mut #pointee)
+            }
+        }
+        CppType::Reference { referent, is_const } => {
+            let referent = rust_type(referent)?;
+            if *is_const {
+                parse_quote!(&#referent)
+            } else {
+                parse_quote!(&mut #referent)
+            }
+        }
+    })
+}
+
+/// Generate a `construct_base` function for each of the base class' constructors, which call the
+/// constructor in place. Overloads are numbered in declaration order (`construct_base_1`, ...),
+/// and constructors with parameters that have no Rust equivalent are skipped. Parameters of
+/// classes in `non_trivial_classes` are taken as `&mut`, as the Itanium ABI passes them through a
+/// pointer to a copy which the caller destroys.
+pub fn generate_constructors(
+    constructors: &[Constructor],
+    non_trivial_classes: &HashSet<String>,
+    class: &Ident,
+    abi: &Abi,
+) -> Vec<impl ToTokens> {
+    constructors
+        .iter()
+        .filter_map(|constructor| {
+            constructor
+                .params
+                .iter()
+                .map(|param| {
+                    let ty = rust_type(param)?;
+                    Some(match param {
+                        CppType::Named(name) if non_trivial_classes.contains(name) => {
+                            parse_quote!(&mut #ty)
+                        }
+                        _ => ty,
+                    })
+                })
+                .collect::<Option<Vec<Type>>>()
+                .map(|params| (constructor, params))
+        })
+        .enumerate()
+        .map(|(i, (constructor, arg_types))| {
+            let ident = match i {
+                0 => format_ident!("construct_base"),
+                _ => format_ident!("construct_base_{}", i),
+            };
+            let symbol = &constructor.symbol;
+            let binding = get_binding_symbol(symbol);
+            let doc = format!(
+                " Run the C++ constructor `{}` on `this`, which must point to uninitialised memory \
+                 for a `{}`, e.g. in the closure passed to `new_boxed`",
+                symbol, class
+            );
+            let arg_names = (0..arg_types.len())
+                .map(|i| format_ident!("arg{}", i))
+                .collect::<Vec<_>>();
+
+            // `pub`, so they're as visible as the struct itself
+            quote!(
+                #[doc = #doc]
+                #[allow(dead_code)]
+                pub unsafe fn #ident(this: *mut #class, #(#arg_names: #arg_types),*) {
+                    #abi {
+                        #[link_name = #symbol]
+                        fn #binding(this: *mut #class, #(#arg_names: #arg_types),*);
+                    }
+
+                    #binding(this, #(#arg_names),*)
+                }
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,120 @@
+use cpp_inherit_core::{CppAbi, VTableElement};
+use quote::{quote, ToTokens};
+use syn::{parse_quote, Abi, ExprPath, Ident, Type};
+
+/// Generate the destructors which go in the base class' destructor slots, along with the
+/// `__cpp_inherit_delete` that `cpp_inherit::Inherits::delete` calls.
+///
+/// The destructors drop the Rust object and then run the base class' destructor, so C++ can
+/// destroy and `delete` objects through a base class pointer. Returns the slots to override.
+///
+/// With `returns_this`, the complete object destructors return `this` like on ARM.
+pub fn generate_destructors(
+    vtable: &[VTableElement],
+    cpp_abi: CppAbi,
+    returns_this: bool,
+    self_type: &Type,
+    class: &Ident,
+    abi: &Abi,
+) -> (Vec<(usize, ExprPath)>, impl ToTokens) {
+    let index = match vtable.iter().position(|entry| entry.name.starts_with('~')) {
+        Some(index) => index,
+        // Without a virtual destructor there's nothing to call the base class' destructor
+        // through, so only the Rust object is dropped
+        None => {
+            return (
+                Vec::new(),
+                quote!(
+                    #[allow(dead_code)]
+                    unsafe fn __cpp_inherit_delete(this: *mut Self) {
+                        ::core::ptr::drop_in_place(this);
+                        ::cpp_inherit::operator_delete(this as *mut ::core::ffi::c_void);
+                    }
+                ),
+            )
+        }
+    };
+    let symbol = &vtable[index].default;
+    let (return_ty, return_this) = if returns_this {
+        (quote!(-> *mut Self), quote!(this))
+    } else {
+        (quote!(), quote!())
+    };
+    let base_return_ty = if returns_this {
+        quote!(-> *mut #class)
+    } else {
+        quote!()
+    };
+
+    match cpp_abi {
+        // The complete object destructor is followed by the deleting destructor
+        CppAbi::Itanium => (
+            vec![
+                (index, parse_quote!(<#self_type>::__cpp_inherit_destructor)),
+                (
+                    index + 1,
+                    parse_quote!(<#self_type>::__cpp_inherit_deleting_destructor),
+                ),
+            ],
+            quote!(
+                #[allow(dead_code)]
+                unsafe #abi fn __cpp_inherit_destructor(this: *mut Self) #return_ty {
+                    #abi {
+                        #[link_name = #symbol]
+                        fn base_destructor(this: *mut #class) #base_return_ty;
+                    }
+
+                    ::core::ptr::drop_in_place(this);
+                    base_destructor(this as *mut #class);
+                    #return_this
+                }
+
+                #[allow(dead_code)]
+                unsafe #abi fn __cpp_inherit_deleting_destructor(this: *mut Self) {
+                    Self::__cpp_inherit_destructor(this);
+                    ::cpp_inherit::operator_delete(this as *mut ::core::ffi::c_void);
+                }
+
+                #[allow(dead_code)]
+                unsafe fn __cpp_inherit_delete(this: *mut Self) {
+                    Self::__cpp_inherit_deleting_destructor(this);
+                }
+            ),
+        ),
+        // A single slot holds a deleting destructor, which only frees the object if the lowest
+        // bit of its flags is set. Calling the base class' with no flags just destroys it.
+        CppAbi::Msvc => (
+            vec![(
+                index,
+                parse_quote!(<#self_type>::__cpp_inherit_deleting_destructor),
+            )],
+            quote!(
+                #[allow(dead_code)]
+                unsafe #abi fn __cpp_inherit_deleting_destructor(
+                    this: *mut Self,
+                    flags: u32,
+                ) -> *mut ::core::ffi::c_void {
+                    #abi {
+                        #[link_name = #symbol]
+                        fn base_destructor(
+                            this: *mut #class,
+                            flags: u32,
+                        ) -> *mut ::core::ffi::c_void;
+                    }
+
+                    ::core::ptr::drop_in_place(this);
+                    base_destructor(this as *mut #class, 0);
+                    if flags & 1 != 0 {
+                        ::cpp_inherit::operator_delete(this as *mut ::core::ffi::c_void);
+                    }
+                    this as *mut ::core::ffi::c_void
+                }
+
+                #[allow(dead_code)]
+                unsafe fn __cpp_inherit_delete(this: *mut Self) {
+                    Self::__cpp_inherit_deleting_destructor(this, 1);
+                }
+            ),
+        ),
+    }
+}
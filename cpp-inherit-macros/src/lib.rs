@@ -0,0 +1,559 @@
+use std::collections::HashSet;
+use std::ops::Deref;
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{ExprPath, Fields, Ident, ImplItem};
+
+mod constructors;
+mod destructors;
+
+mod method_helpers;
+use method_helpers::{
+    filter_overrides, override_options, remove_override_attr, resolve_override, NonConstSelf,
+};
+
+mod parsers;
+use parsers::{InheritAttr, InheritImplAttr, NamedField};
+
+mod shims;
+use shims::OnPanic;
+
+mod vtable;
+use vtable::generate_vtable_const;
+
+#[proc_macro_attribute]
+pub fn inherit_from(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut struct_def = syn::parse_macro_input!(item as syn::ItemStruct);
+    let InheritAttr { ty, pinned } = syn::parse_macro_input!(attr as InheritAttr);
+
+    let fields = match struct_def.fields {
+        Fields::Named(ref mut fields) => &mut fields.named,
+        Fields::Unit => {
+            struct_def.fields = Fields::Named(syn::parse_quote!({}));
+            if let Fields::Named(ref mut fields) = struct_def.fields {
+                &mut fields.named
+            } else {
+                unreachable!()
+            }
+        }
+        _ => panic!("Tuple-type structs cannot inherit from classes"),
+    };
+
+    let base_field: NamedField = syn::parse_quote!(
+        _base: #ty
+    );
+
+    fields.insert(0, base_field.0);
+
+    // Every other field is a parameter of the constructors
+    let field_names = fields
+        .iter()
+        .skip(1)
+        .map(|field| field.ident.clone().unwrap())
+        .collect::<Vec<_>>();
+    let field_types = fields
+        .iter()
+        .skip(1)
+        .map(|field| field.ty.clone())
+        .collect::<Vec<_>>();
+
+    if pinned {
+        let pinned_field: NamedField = syn::parse_quote!(
+            _pinned: ::core::marker::PhantomPinned
+        );
+        fields.push(pinned_field.0);
+    }
+
+    let struct_name = &struct_def.ident;
+    let vis = &struct_def.vis;
+    let (impl_generics, ty_generics, where_clause) = struct_def.generics.split_for_impl();
+
+    // Everything which needs the vtable only exists once `inherit_from_impl` has implemented
+    // `Inherits`. The bound is higher-ranked so that it's only checked where the items are used,
+    // and the struct can be used without `inherit_from_impl`.
+    let mut vtable_where_clause = struct_def.generics.clone().make_where_clause().clone();
+    vtable_where_clause.predicates.push(syn::parse_quote!(
+        for<'__cpp_inherit> Self: ::cpp_inherit::Inherits<Base = #ty>
+    ));
+
+    struct_def.attrs.push(syn::parse_quote! {
+        #[repr(C)]
+    });
+
+    // Pinned types can only be constructed in place, as the base is then never moved
+    let with_base = if pinned {
+        None
+    } else {
+        Some(quote!(
+            /// Construct from an instance of the base class, pointing its vtable pointer at this
+            /// type's vtable (from `inherit_from_impl`) so C++ calls the overrides
+            #[allow(dead_code)]
+            #vis fn with_base(mut _base: #ty, #(#field_names: #field_types),*) -> Self {
+                unsafe {
+                    *(&mut _base as *mut #ty as *mut *const ()) =
+                        <Self as ::cpp_inherit::Inherits>::VTABLE;
+                }
+
+                Self {
+                    _base,
+                    #(#field_names),*
+                }
+            }
+        ))
+    };
+
+    // Mutable references to pinned types stay pinned
+    let mut_ref = if pinned {
+        quote!(::core::pin::Pin<&'a mut Self>)
+    } else {
+        quote!(&'a mut Self)
+    };
+    let into_mut_ref = if pinned {
+        quote!(::core::pin::Pin::new_unchecked(&mut *(base as *mut Self)))
+    } else {
+        quote!(&mut *(base as *mut Self))
+    };
+
+    quote!(
+        #struct_def
+
+        impl #impl_generics #struct_name #ty_generics #vtable_where_clause {
+            #with_base
+
+            /// Allocate and construct in place, with `construct` running a C++ constructor on the
+            /// uninitialised base (e.g. `|base| unsafe { Self::construct_base(base, ...) }`, from
+            /// `inherit_from_impl`) before the vtable pointer is pointed at this type's vtable.
+            ///
+            /// The object is allocated with `operator new` and pinned, as constructors may keep its
+            /// address. Dropping the box runs the base class' destructor.
+            #[allow(dead_code)]
+            #vis fn new_boxed(
+                construct: impl FnOnce(*mut #ty),
+                #(#field_names: #field_types),*
+            ) -> ::core::pin::Pin<::cpp_inherit::CppBox<Self>> {
+                unsafe {
+                    ::cpp_inherit::CppBox::new_in_place(|this| {
+                        Self::new_in_place(this, construct, #(#field_names),*)
+                    })
+                }
+            }
+
+            /// Construct in place at `this`, like `new_boxed`, for objects which aren't boxed
+            ///
+            /// # Safety
+            ///
+            /// `this` must be valid for writes and aligned. The object must then be treated as
+            /// pinned: it can't be moved, and its memory can't be reused until it has been
+            /// dropped, as the C++ constructor may have kept its address.
+            #[allow(dead_code)]
+            #vis unsafe fn new_in_place(
+                this: *mut Self,
+                construct: impl FnOnce(*mut #ty),
+                #(#field_names: #field_types),*
+            ) {
+                let base = ::core::ptr::addr_of_mut!((*this)._base);
+                construct(base);
+                *(base as *mut *const ()) = <Self as ::cpp_inherit::Inherits>::VTABLE;
+                #(
+                    ::core::ptr::addr_of_mut!((*this).#field_names).write(#field_names);
+                )*
+            }
+
+            /// Get the object a base class pointer (e.g. passed from C++) points into, if it's
+            /// one of this type, which is when its vtable pointer points at this type's vtable
+            ///
+            /// # Safety
+            ///
+            /// `base` must be null or point to a valid base class object. If it has this type's
+            /// vtable, it must be the base of an object of this type which lives for `'a`.
+            #[allow(dead_code)]
+            #vis unsafe fn from_base_ptr<'a>(base: *const #ty) -> Option<&'a Self> {
+                if !base.is_null() && <Self as ::cpp_inherit::Inherits>::is_vtable(*(base as *const _)) {
+                    Some(Self::from_base_ptr_unchecked(base))
+                } else {
+                    None
+                }
+            }
+
+            /// Mutable version of `from_base_ptr`
+            ///
+            /// # Safety
+            ///
+            /// As for `from_base_ptr`, and the object can't be otherwise borrowed for `'a`.
+            #[allow(dead_code)]
+            #vis unsafe fn from_base_ptr_mut<'a>(base: *mut #ty) -> Option<#mut_ref> {
+                if !base.is_null() && <Self as ::cpp_inherit::Inherits>::is_vtable(*(base as *const _)) {
+                    Some(Self::from_base_ptr_unchecked_mut(base))
+                } else {
+                    None
+                }
+            }
+
+            /// Get the object a base class pointer points into without checking its type
+            ///
+            /// # Safety
+            ///
+            /// `base` must be the base of an object of this type which lives for `'a`.
+            #[allow(dead_code)]
+            #vis unsafe fn from_base_ptr_unchecked<'a>(base: *const #ty) -> &'a Self {
+                // `_base` is the first field of the `#[repr(C)]` struct
+                &*(base as *const Self)
+            }
+
+            /// Mutable version of `from_base_ptr_unchecked`
+            ///
+            /// # Safety
+            ///
+            /// As for `from_base_ptr_unchecked`, and the object can't be otherwise borrowed for
+            /// `'a`.
+            #[allow(dead_code)]
+            #vis unsafe fn from_base_ptr_unchecked_mut<'a>(base: *mut #ty) -> #mut_ref {
+                #into_mut_ref
+            }
+        }
+
+        impl #impl_generics ::core::ops::Deref for #struct_name #ty_generics #where_clause {
+            type Target = #ty;
+
+            fn deref(&self) -> &Self::Target {
+                &self._base
+            }
+        }
+
+        impl #impl_generics ::core::ops::DerefMut for #struct_name #ty_generics #where_clause {
+            fn deref_mut(&mut self) -> &mut Self::Target {
+                &mut self._base
+            }
+        }
+    )
+    .into()
+}
+
+#[proc_macro_attribute]
+pub fn inherit_from_impl(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut impl_block = syn::parse_macro_input!(item as syn::ItemImpl);
+    let InheritImplAttr {
+        class,
+        header,
+        abi,
+        on_panic,
+        non_const_self,
+        catch_exceptions,
+        ..
+    } = syn::parse_macro_input!(attr as InheritImplAttr);
+
+    let header = header.value();
+
+    // List of methods with #[overridden] attrbiute
+    let mut override_items = impl_block
+        .items
+        .iter_mut()
+        .filter_map(|item| {
+            if let ImplItem::Method(ref mut method) = item {
+                filter_overrides(method)
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let target = cpp_inherit_core::target_triple();
+    let vtable_info = vtable::get_vtable_info(&header, &class.to_string(), &target);
+
+    // Overrides are called through shims using the C++ member function calling convention
+    let abi = match abi {
+        Some(abi) => syn::Abi {
+            extern_token: Default::default(),
+            name: Some(abi),
+        },
+        None => syn::Abi {
+            extern_token: Default::default(),
+            name: Some(syn::LitStr::new(
+                vtable_info.method_abi(&target),
+                proc_macro::Span::call_site().into(),
+            )),
+        },
+    };
+
+    // What each override does on panic, which can be set for the whole impl block or per method
+    let on_panic = match on_panic.as_ref().map(OnPanic::parse).transpose() {
+        Ok(on_panic) => on_panic.unwrap_or(OnPanic::Abort),
+        Err(error) => return error.to_compile_error().into(),
+    };
+    let override_options = override_items
+        .iter()
+        .map(|method| {
+            let options = override_options(method)?;
+            let on_panic = options
+                .on_panic
+                .as_ref()
+                .map_or(Ok(on_panic), OnPanic::parse)?;
+            Ok((on_panic, options))
+        })
+        .collect::<syn::Result<Vec<_>>>();
+    let override_options = match override_options {
+        Ok(options) => options,
+        Err(error) => return error.to_compile_error().into(),
+    };
+    let non_const_self = match non_const_self.as_ref().map(NonConstSelf::parse).transpose() {
+        Ok(non_const_self) => non_const_self.unwrap_or(NonConstSelf::Any),
+        Err(error) => return error.to_compile_error().into(),
+    };
+
+    // Remove fake overridden attributes
+    override_items.iter_mut().for_each(remove_override_attr);
+
+    // List of method override signatures
+    let override_list = override_items
+        .into_iter()
+        .map(|method| method.sig.clone())
+        .zip(override_options)
+        .collect::<Vec<_>>();
+
+    match vtable_info.get(&class.to_string()) {
+        Some(base_type_vtable) => {
+            // Slots are filled by index, so every slot needs to be known
+            if let Some(index) =
+                (0..base_type_vtable.len()).find(|&i| base_type_vtable[i].pos != i as u64)
+            {
+                panic!(
+                    "Slot {} of the vtable for `{}` could not be found",
+                    index, class
+                );
+            }
+
+            // Generate a vtable before overrides
+            let base_vtable: Vec<Option<ExprPath>> = vec![None; base_type_vtable.len()];
+
+            let mut vtable = base_vtable;
+
+            let self_type = &impl_block.self_ty;
+
+            let mut super_calls = vec![];
+            let mut override_shims = vec![];
+
+            // Apply each override to the base vtable
+            for (sig, (on_panic, options)) in &override_list {
+                let index = match resolve_override(base_type_vtable, sig, options, non_const_self) {
+                    Ok(index) => index,
+                    Err(error) => return error.to_compile_error().into(),
+                };
+                if vtable[index].is_some() {
+                    return syn::Error::new_spanned(
+                        &sig.ident,
+                        format!(
+                            "`{}` is overridden more than once",
+                            base_type_vtable[index].name
+                        ),
+                    )
+                    .to_compile_error()
+                    .into();
+                }
+
+                let shim = shims::get_shim_ident(&sig.ident);
+                vtable[index] = Some(syn::parse_quote!(<#self_type>::#shim));
+                let indirect_return = match shims::returns_indirectly(
+                    sig,
+                    &base_type_vtable[index],
+                    &vtable_info,
+                    &target,
+                ) {
+                    Ok(indirect_return) => indirect_return,
+                    Err(error) => return error.to_compile_error().into(),
+                };
+                let covariant =
+                    match shims::covariant_return(sig, &base_type_vtable[index], &vtable_info) {
+                        Ok(covariant) => covariant,
+                        Err(error) => return error.to_compile_error().into(),
+                    };
+                override_shims.push(shims::generate_override_shim(
+                    sig,
+                    *on_panic,
+                    &abi,
+                    indirect_return,
+                    covariant.as_ref(),
+                ));
+
+                // Allow the override to call into the implementation it replaces, which returns
+                // the base class for covariant overrides. Pure virtual methods have none.
+                if base_type_vtable[index].must_override() {
+                    continue;
+                }
+                let mut super_sig = sig.clone();
+                if let Some(covariant) = &covariant {
+                    let return_ty = &covariant.return_ty;
+                    super_sig.output = syn::parse_quote!(-> #return_ty);
+                }
+                super_calls.push(vtable::generate_super_call(
+                    &super_sig,
+                    &base_type_vtable[index].default,
+                    &abi,
+                    catch_exceptions || options.catch_exceptions,
+                    indirect_return,
+                ));
+            }
+
+            // Destructors are generated, so C++ can destroy and delete the Rust object
+            let (destructor_slots, destructors) = destructors::generate_destructors(
+                base_type_vtable,
+                vtable_info.abi,
+                vtable_info.structors_return_this(&target),
+                self_type,
+                &class,
+                &abi,
+            );
+            for (index, destructor) in destructor_slots {
+                vtable[index] = Some(destructor);
+            }
+
+            // Pure virtual methods (or ones without a symbol to link against) have nothing to fall
+            // back on, so report every one that wasn't overridden at once, like rustc does for
+            // missing trait items
+            let missing = vtable
+                .iter()
+                .zip(base_type_vtable.iter())
+                .filter(|(slot, entry)| slot.is_none() && entry.must_override())
+                .map(|(_, entry)| format!("`{}`", entry.name))
+                .collect::<Vec<_>>();
+
+            if !missing.is_empty() {
+                return syn::Error::new_spanned(
+                    self_type,
+                    format!(
+                        "not all pure virtual methods of `{}` are overridden, missing: {}",
+                        class,
+                        missing.join(", ")
+                    ),
+                )
+                .to_compile_error()
+                .into();
+            }
+
+            // Methods which aren't overridden can be called on the base class' implementation too,
+            // where their types are known. Overloads are numbered like `super_x_1`.
+            let mut super_idents = override_list
+                .iter()
+                .map(|(sig, _)| format!("super_{}", sig.ident))
+                .collect::<HashSet<_>>();
+            for (slot, entry) in vtable.iter().zip(base_type_vtable) {
+                if slot.is_some() || entry.must_override() {
+                    continue;
+                }
+                let ident = (0..)
+                    .map(|i| match i {
+                        0 => entry.name.clone(),
+                        _ => format!("{}_{}", entry.name, i),
+                    })
+                    .find(|name| !super_idents.contains(&format!("super_{}", name)))
+                    .unwrap();
+                let ident = match syn::parse_str::<Ident>(&ident) {
+                    Ok(ident) => ident,
+                    // e.g. operators
+                    Err(_) => continue,
+                };
+                let sig = match vtable::base_method_signature(&ident, entry, &vtable_info) {
+                    Some(sig) => sig,
+                    None => continue,
+                };
+                let indirect_return =
+                    match shims::returns_indirectly(&sig, entry, &vtable_info, &target) {
+                        Ok(indirect_return) => indirect_return,
+                        Err(_) => continue,
+                    };
+                super_idents.insert(format!("super_{}", ident));
+                super_calls.push(vtable::generate_super_call(
+                    &sig,
+                    &entry.default,
+                    &abi,
+                    catch_exceptions,
+                    indirect_return,
+                ));
+            }
+
+            let mut bindings_to_gen = vec![];
+
+            let vtable = vtable
+                .into_iter()
+                .enumerate()
+                .map(|(i, x)| {
+                    x.unwrap_or_else(|| {
+                        bindings_to_gen.push(base_type_vtable[i].default.deref());
+
+                        let binding = vtable::get_binding_symbol(&base_type_vtable[i].default);
+                        syn::parse_quote!(#binding)
+                    })
+                })
+                .collect();
+
+            let vtable_const = generate_vtable_const(
+                vtable,
+                self_type,
+                &impl_block.generics,
+                vtable_info.address_size,
+            );
+
+            let bindings = bindings_to_gen
+                .into_iter()
+                .map(|symbol| vtable::generate_binding(symbol, &abi));
+
+            let constructors = constructors::generate_constructors(
+                vtable_info.constructors(&class.to_string()),
+                &vtable_info.non_trivial_classes,
+                &class,
+                &abi,
+            );
+
+            let (impl_generics, _, where_clause) = impl_block.generics.split_for_impl();
+
+            quote!(
+                #impl_block
+
+                #vtable_const
+
+                // A polymorphic class's vtable pointer is at the start of the object, whichever
+                // field bindgen names it
+                const _: () = assert!(
+                    ::core::mem::size_of::<#class>() >= ::core::mem::size_of::<*const ()>()
+                );
+
+                // Gives the struct the items `inherit_from` generates which need the vtable
+                unsafe impl #impl_generics ::cpp_inherit::Inherits for #self_type #where_clause {
+                    type Base = #class;
+
+                    const VTABLE: *const () = Self::VTABLE_ as *const ();
+
+                    unsafe fn is_vtable(vtable: *const ()) -> bool {
+                        Self::__cpp_inherit_is_vtable(vtable)
+                    }
+
+                    unsafe fn delete(this: *mut Self) {
+                        Self::__cpp_inherit_delete(this)
+                    }
+                }
+
+                impl #impl_generics #self_type #where_clause {
+                    #(
+                        #override_shims
+                    )*
+
+                    #(
+                        #super_calls
+                    )*
+
+                    #(
+                        #constructors
+                    )*
+
+                    #destructors
+                }
+
+                #(
+                    #bindings
+                )*
+            )
+            .into()
+        }
+        None => panic!("Class does not exist in header"), // add compiler error for class not existing in header
+    }
+}
@@ -0,0 +1,299 @@
+use cpp_inherit_core::{CppType, Qualifiers, VTableElement};
+use quote::ToTokens;
+use syn::{Attribute, FnArg, ImplItemMethod, LitStr, Signature, Type};
+
+use crate::constructors::rust_type;
+use crate::parsers::OverrideAttr;
+
+fn is_override_attr(attr: &Attribute) -> bool {
+    attr.path
+        .get_ident()
+        .map(|ident| *ident == "overridden")
+        .unwrap_or(false)
+}
+
+pub fn filter_overrides(method: &mut ImplItemMethod) -> Option<&mut ImplItemMethod> {
+    if method.attrs.iter().any(is_override_attr) {
+        Some(method)
+    } else {
+        None
+    }
+}
+
+pub fn remove_override_attr(method: &mut &mut ImplItemMethod) {
+    method.attrs.retain(|attr| !is_override_attr(attr));
+}
+
+/// The options given to a method's `#[overridden(...)]` attribute
+pub fn override_options(method: &ImplItemMethod) -> syn::Result<OverrideAttr> {
+    match method.attrs.iter().find(|attr| is_override_attr(attr)) {
+        Some(attr) if !attr.tokens.is_empty() => attr.parse_args(),
+        _ => Ok(OverrideAttr::default()),
+    }
+}
+
+/// Which receivers overrides of non-const C++ methods may take
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NonConstSelf {
+    /// `&self` or `&mut self` (the default)
+    Any,
+    /// Only `&mut self`, so `&self` always means overriding a const method
+    Mut,
+}
+
+impl NonConstSelf {
+    pub fn parse(value: &LitStr) -> syn::Result<Self> {
+        match value.value().as_str() {
+            "any" => Ok(NonConstSelf::Any),
+            "mut" => Ok(NonConstSelf::Mut),
+            _ => Err(syn::Error::new(
+                value.span(),
+                "non_const_self must be \"any\" or \"mut\"",
+            )),
+        }
+    }
+}
+
+/// Whether an override takes `&mut self`, or an error if it doesn't take `self` by reference
+fn takes_mut_self(sig: &Signature) -> syn::Result<bool> {
+    match sig.inputs.first() {
+        Some(FnArg::Receiver(receiver)) if receiver.reference.is_some() => {
+            Ok(receiver.mutability.is_some())
+        }
+        _ => Err(syn::Error::new_spanned(
+            sig,
+            "Overridden methods must take self by reference",
+        )),
+    }
+}
+
+/// How a type is written, as far as telling overloads apart goes: paths by their last segment,
+/// so `c_void` and `::core::ffi::c_void` are the same, and references without their lifetime
+fn type_key(ty: &Type) -> String {
+    match ty {
+        Type::Reference(reference) => format!(
+            "&{}{}",
+            if reference.mutability.is_some() {
+                "mut "
+            } else {
+                ""
+            },
+            type_key(&reference.elem)
+        ),
+        Type::Ptr(pointer) => format!(
+            "*{} {}",
+            if pointer.mutability.is_some() {
+                "mut"
+            } else {
+                "const"
+            },
+            type_key(&pointer.elem)
+        ),
+        Type::Path(path) if path.qself.is_none() => match path.path.segments.last() {
+            Some(segment) => segment.ident.to_string(),
+            None => String::new(),
+        },
+        Type::Paren(paren) => type_key(&paren.elem),
+        Type::Group(group) => type_key(&group.elem),
+        _ => ty.to_token_stream().to_string(),
+    }
+}
+
+/// Whether an override's parameter is the type of a C++ method's parameter. Classes can also be
+/// taken by `&mut`, as those which aren't trivial for the purposes of calls are.
+fn same_param(rust: &Type, cpp: &CppType) -> bool {
+    let cpp_type = match rust_type(cpp) {
+        Some(cpp_type) => cpp_type,
+        None => return false,
+    };
+    let key = type_key(rust);
+    key == type_key(&cpp_type)
+        || matches!(cpp, CppType::Named(_)) && key == format!("&mut {}", type_key(&cpp_type))
+}
+
+/// Keep the candidates `predicate` accepts, unless it accepts none of them
+fn narrow(
+    candidates: &mut Vec<(usize, &VTableElement)>,
+    predicate: impl Fn(&VTableElement) -> bool,
+) {
+    let matching = candidates
+        .iter()
+        .copied()
+        .filter(|(_, entry)| predicate(entry))
+        .collect::<Vec<_>>();
+    if !matching.is_empty() {
+        *candidates = matching;
+    }
+}
+
+/// Find the vtable slot an override replaces. Overloads are told apart by `symbol` if it's given,
+/// otherwise by the number and types of the parameters, then by `qualifiers` or the receiver:
+/// `&self` prefers a const overload and `&mut self` a non-const one.
+pub fn resolve_override(
+    vtable: &[VTableElement],
+    sig: &Signature,
+    options: &OverrideAttr,
+    non_const_self: NonConstSelf,
+) -> syn::Result<usize> {
+    let name = options
+        .name
+        .as_ref()
+        .map_or_else(|| sig.ident.to_string(), LitStr::value);
+    let mut_self = takes_mut_self(sig)?;
+
+    let mut candidates = vtable
+        .iter()
+        .enumerate()
+        .filter(|(_, entry)| entry.name == name)
+        .collect::<Vec<_>>();
+    if candidates.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &sig.ident,
+            format!(
+                "Cannot override `{}`, a virtual method that doesn't exist in the original vtable",
+                name
+            ),
+        ));
+    }
+
+    // Parameters are written as the `super_` helpers' are, `_` if they have no Rust equivalent
+    let describe = |entry: &VTableElement| {
+        let params = entry
+            .params
+            .iter()
+            .map(|param| match param.as_ref().and_then(rust_type) {
+                Some(ty) => ty.to_token_stream().to_string(),
+                None => "_".to_owned(),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        match entry.qualifiers.to_string().as_str() {
+            "" => format!("`{}({})`", entry.name, params),
+            qualifiers => format!("`{}({}) {}`", entry.name, params, qualifiers),
+        }
+    };
+    let list = |candidates: &[(usize, &VTableElement)]| {
+        candidates
+            .iter()
+            .map(|(_, entry)| format!("{} (`{}`)", describe(entry), entry.default))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    if let Some(symbol) = &options.symbol {
+        candidates.retain(|(_, entry)| entry.default == symbol.value());
+        if candidates.is_empty() {
+            return Err(syn::Error::new(
+                symbol.span(),
+                format!(
+                    "No overload of `{}` has the symbol `{}`, only: {}",
+                    name,
+                    symbol.value(),
+                    list(
+                        &vtable
+                            .iter()
+                            .enumerate()
+                            .filter(|(_, entry)| entry.name == name)
+                            .collect::<Vec<_>>()
+                    )
+                ),
+            ));
+        }
+    }
+
+    if let Some(qualifiers) = &options.qualifiers {
+        let wanted = qualifiers
+            .value()
+            .parse::<Qualifiers>()
+            .map_err(|error| syn::Error::new(qualifiers.span(), error))?;
+        let all = candidates.clone();
+        candidates.retain(|(_, entry)| entry.qualifiers == wanted);
+        if candidates.is_empty() {
+            return Err(syn::Error::new(
+                qualifiers.span(),
+                format!(
+                    "`{}` has no overload qualified `{}`, only: {}",
+                    name,
+                    wanted,
+                    all.iter()
+                        .map(|(_, entry)| describe(entry))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            ));
+        }
+    }
+
+    // Parameter types which are written differently (e.g. `c_int` for `i32`) don't rule out an
+    // overload, so only those which match exactly are preferred
+    let params = sig
+        .inputs
+        .iter()
+        .filter_map(|input| match input {
+            FnArg::Typed(param) => Some(&*param.ty),
+            FnArg::Receiver(_) => None,
+        })
+        .collect::<Vec<_>>();
+    narrow(&mut candidates, |entry| entry.params.len() == params.len());
+    narrow(&mut candidates, |entry| {
+        entry
+            .params
+            .iter()
+            .zip(&params)
+            .all(|(cpp, rust)| cpp.as_ref().is_some_and(|cpp| same_param(rust, cpp)))
+    });
+    if options.qualifiers.is_none() {
+        narrow(&mut candidates, |entry| {
+            entry.qualifiers.is_const != mut_self
+        });
+    }
+
+    let (index, entry) = match candidates[..] {
+        [candidate] => candidate,
+        _ => {
+            // Only suggest `qualifiers` if it's what tells the overloads apart
+            let qualifiers_differ = candidates.iter().enumerate().all(|(i, (_, entry))| {
+                candidates[..i]
+                    .iter()
+                    .all(|(_, other)| other.qualifiers != entry.qualifiers)
+            });
+            let option = if qualifiers_differ {
+                "`#[overridden(qualifiers = \"...\")]` or `#[overridden(symbol = \"...\")]`"
+            } else {
+                "`#[overridden(symbol = \"...\")]`"
+            };
+            return Err(syn::Error::new_spanned(
+                &sig.ident,
+                format!(
+                    "`{}` is overloaded as {}, pick one with {}",
+                    name,
+                    list(&candidates),
+                    option
+                ),
+            ));
+        }
+    };
+
+    // A const method can't hand out `&mut self`, and `non_const_self = "mut"` reserves `&self`
+    // for const methods
+    if entry.qualifiers.is_const && mut_self {
+        return Err(syn::Error::new_spanned(
+            sig,
+            format!(
+                "{} is const, so it must be overridden with `&self`",
+                describe(entry)
+            ),
+        ));
+    }
+    if !entry.qualifiers.is_const && !mut_self && non_const_self == NonConstSelf::Mut {
+        return Err(syn::Error::new_spanned(
+            sig,
+            format!(
+                "{} isn't const, so it must be overridden with `&mut self`",
+                describe(entry)
+            ),
+        ));
+    }
+
+    Ok(index)
+}
@@ -0,0 +1,143 @@
+use syn::{Field, Ident, LitStr, Token, Type};
+
+pub struct InheritAttr {
+    pub ty: Type,
+    /// Make the struct `!Unpin` and only construct it in place, e.g. `pinned`
+    pub pinned: bool,
+}
+
+impl syn::parse::Parse for InheritAttr {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let mut attr = Self {
+            ty: input.parse()?,
+            pinned: false,
+        };
+
+        // Optional trailing flags
+        while input.parse::<Option<Token![,]>>()?.is_some() && !input.is_empty() {
+            let flag: Ident = input.parse()?;
+            match flag.to_string().as_str() {
+                "pinned" => attr.pinned = true,
+                _ => return Err(syn::Error::new(flag.span(), "unknown inherit_from option")),
+            }
+        }
+
+        Ok(attr)
+    }
+}
+
+pub struct InheritImplAttr {
+    pub class: Ident,
+    _comma: Token![,],
+    pub header: LitStr,
+    /// Calling convention for overrides, e.g. `abi = "thiscall"`
+    pub abi: Option<LitStr>,
+    /// What overrides do if they panic, e.g. `on_panic = "default"`
+    pub on_panic: Option<LitStr>,
+    /// Which receivers overrides of non-const methods may take, e.g. `non_const_self = "mut"`
+    pub non_const_self: Option<LitStr>,
+    /// Make every `super_` call return a `Result`, catching C++ exceptions, e.g. `catch_exceptions`
+    pub catch_exceptions: bool,
+}
+
+impl syn::parse::Parse for InheritImplAttr {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let mut attr = Self {
+            class: input.parse()?,
+            _comma: input.parse()?,
+            header: input.parse()?,
+            abi: None,
+            on_panic: None,
+            non_const_self: None,
+            catch_exceptions: false,
+        };
+
+        // Optional trailing `key = "value"` settings and flags
+        while input.parse::<Option<Token![,]>>()?.is_some() && !input.is_empty() {
+            let key: Ident = input.parse()?;
+            if key == "catch_exceptions" {
+                attr.catch_exceptions = true;
+                continue;
+            }
+            input.parse::<Token![=]>()?;
+            match key.to_string().as_str() {
+                "abi" => attr.abi = Some(input.parse()?),
+                "on_panic" => attr.on_panic = Some(input.parse()?),
+                "non_const_self" => attr.non_const_self = Some(input.parse()?),
+                _ => {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        "unknown inherit_from_impl option",
+                    ))
+                }
+            }
+        }
+
+        Ok(attr)
+    }
+}
+
+/// The arguments of `#[overridden(...)]`
+#[derive(Default)]
+pub struct OverrideAttr {
+    /// Overrides the impl block's `on_panic`
+    pub on_panic: Option<LitStr>,
+    /// Make the `super_` call return a `Result`, catching C++ exceptions, e.g. `catch_exceptions`
+    pub catch_exceptions: bool,
+    /// The C++ method overridden, if it isn't named the same as the Rust one, e.g. `name = "x"`
+    pub name: Option<LitStr>,
+    /// The cv and ref qualifiers of the overload to override, e.g. `qualifiers = "const &"`
+    pub qualifiers: Option<LitStr>,
+    /// The symbol of the overload to override, e.g. `symbol = "_ZN4base1xEi"`
+    pub symbol: Option<LitStr>,
+}
+
+impl syn::parse::Parse for OverrideAttr {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let mut attr = Self::default();
+
+        // `key = "value"` settings and flags
+        while !input.is_empty() {
+            let key: Ident = input.parse()?;
+            match key.to_string().as_str() {
+                "on_panic" => {
+                    input.parse::<Token![=]>()?;
+                    attr.on_panic = Some(input.parse()?);
+                }
+                "catch_exceptions" => attr.catch_exceptions = true,
+                "name" => {
+                    input.parse::<Token![=]>()?;
+                    attr.name = Some(input.parse()?);
+                }
+                "qualifiers" => {
+                    input.parse::<Token![=]>()?;
+                    attr.qualifiers = Some(input.parse()?);
+                }
+                "symbol" => {
+                    input.parse::<Token![=]>()?;
+                    attr.symbol = Some(input.parse()?);
+                }
+                _ => return Err(syn::Error::new(key.span(), "unknown overridden option")),
+            }
+            if input.parse::<Option<Token![,]>>()?.is_none() {
+                break;
+            }
+        }
+
+        Ok(attr)
+    }
+}
+
+pub struct NamedField(pub Field);
+
+impl syn::parse::Parse for NamedField {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        Ok(Self(Field::parse_named(input)?))
+    }
+}
+
+impl From<NamedField> for Field {
+    fn from(val: NamedField) -> Self {
+        val.0
+    }
+}
@@ -0,0 +1,379 @@
+use cpp_inherit_core::{target_arch, BaseOffset, CppAbi, CppType, VTableElement, VTableInfo};
+use quote::{format_ident, quote, ToTokens};
+use syn::{parse_quote, Abi, FnArg, Ident, LitStr, ReturnType, Signature, Type};
+
+use crate::constructors::rust_type;
+
+/// What an override does when it panics, as unwinding into C++ is undefined behaviour
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnPanic {
+    /// Abort the process (the default)
+    Abort,
+    /// Print a message and return `Default::default()`
+    Default,
+    /// Rethrow the panic as a `std::runtime_error` (needs cpp-inherit's `exceptions` feature)
+    Throw,
+}
+
+impl OnPanic {
+    pub fn parse(value: &LitStr) -> syn::Result<Self> {
+        match value.value().as_str() {
+            "abort" => Ok(OnPanic::Abort),
+            "default" => Ok(OnPanic::Default),
+            "throw" => Ok(OnPanic::Throw),
+            _ => Err(syn::Error::new(
+                value.span(),
+                "on_panic must be \"abort\", \"default\" or \"throw\"",
+            )),
+        }
+    }
+}
+
+/// The `-unwind` version of a calling convention, for calls which C++ exceptions unwind through
+pub fn unwind_abi(abi: &Abi) -> Abi {
+    let name = abi.name.as_ref().map(LitStr::value);
+    let name = format!("{}-unwind", name.as_deref().unwrap_or("C"));
+    Abi {
+        extern_token: abi.extern_token,
+        name: Some(LitStr::new(&name, abi.extern_token.span)),
+    }
+}
+
+/// The name of the shim C++ calls for an override
+pub fn get_shim_ident(method: &Ident) -> Ident {
+    format_ident!("__cpp_inherit_override_{}", method)
+}
+
+/// Whether the method an override replaces returns through a hidden pointer to the return value,
+/// as the Itanium ABI does for classes which are non-trivial for the purposes of calls.
+///
+/// Such classes are also passed through a pointer to a temporary, which the caller still owns and
+/// destroys, so parameters of them must be taken as `&mut T`. On MSVC overrides taking or
+/// returning anything but a scalar are an error.
+pub fn returns_indirectly(
+    sig: &Signature,
+    entry: &VTableElement,
+    info: &VTableInfo,
+    target: &str,
+) -> syn::Result<bool> {
+    // MSVC returns every class type from a member function through a hidden pointer, even trivial
+    // ones, and passes some by pointer too (e.g. on x64 those which aren't 1, 2, 4 or 8 bytes),
+    // but PDBs don't record the types, so only allow types which can't be classes
+    if info.abi == CppAbi::Msvc {
+        for arg in sig.inputs.iter().skip(1) {
+            match arg {
+                FnArg::Typed(arg) if !is_scalar(&arg.ty) => {
+                    return Err(syn::Error::new_spanned(
+                        &arg.ty,
+                        "overrides can only take scalar types (integers, floats, `bool`, pointers \
+                         and references) on MSVC, as it passes some classes through a pointer",
+                    ))
+                }
+                _ => {}
+            }
+        }
+        return match &sig.output {
+            ReturnType::Type(_, ty) if !is_scalar(ty) => Err(syn::Error::new_spanned(
+                ty,
+                "overrides can only return scalar types (integers, floats, `bool`, pointers and \
+                 references) on MSVC, as it returns classes through a pointer",
+            )),
+            _ => Ok(false),
+        };
+    }
+
+    let params = sig.inputs.iter().skip(1).zip(&entry.params);
+    for (arg, param) in params {
+        let (arg, class) = match (arg, param) {
+            (FnArg::Typed(arg), Some(CppType::Named(class))) => (arg, class),
+            _ => continue,
+        };
+        if !info.non_trivial_classes.contains(class) {
+            continue;
+        }
+        match &*arg.ty {
+            Type::Reference(reference) if reference.mutability.is_some() => {}
+            ty => {
+                return Err(syn::Error::new_spanned(
+                    ty,
+                    format!(
+                        "C++ passes `{}` through a pointer to a copy which it destroys after the \
+                         call, so it must be taken as `&mut {}`",
+                        class, class
+                    ),
+                ))
+            }
+        }
+    }
+
+    match &entry.return_type {
+        Some(CppType::Named(class)) if info.non_trivial_classes.contains(class) => {
+            if let ReturnType::Default = sig.output {
+                return Err(syn::Error::new_spanned(
+                    sig,
+                    format!(
+                        "`{}` returns `{}`, which the override must too",
+                        entry.name, class
+                    ),
+                ));
+            }
+            // The pointer isn't passed as the first argument on these
+            match target_arch(target) {
+                arch @ ("aarch64" | "x86") => Err(syn::Error::new_spanned(
+                    &sig.output,
+                    format!(
+                        "returning `{}` by value isn't supported on {} yet",
+                        class, arch
+                    ),
+                )),
+                _ => Ok(true),
+            }
+        }
+        _ => Ok(false),
+    }
+}
+
+/// Whether a type is returned in registers on every ABI: the unit type, integers, floats, `bool`,
+/// `char`, pointers, references and the `c_*` aliases
+fn is_scalar(ty: &Type) -> bool {
+    const PRIMITIVES: &[&str] = &[
+        "bool", "char", "i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32", "u64",
+        "u128", "usize", "f32", "f64",
+    ];
+
+    match ty {
+        Type::Paren(ty) => is_scalar(&ty.elem),
+        Type::Group(ty) => is_scalar(&ty.elem),
+        Type::Tuple(tuple) => tuple.elems.is_empty(),
+        Type::Ptr(_) | Type::Reference(_) | Type::BareFn(_) => true,
+        Type::Path(path) if path.qself.is_none() => match path.path.segments.last() {
+            Some(last) if last.arguments.is_empty() => {
+                let name = last.ident.to_string();
+                if path.path.segments.len() == 1 && PRIMITIVES.contains(&name.as_str()) {
+                    true
+                } else {
+                    name.starts_with("c_") && name != "c_void"
+                }
+            }
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// An override returning a pointer or reference to a class derived from the one the overridden
+/// method returns, which has to be adjusted to point at the base like C++'s return thunks do
+pub struct CovariantReturn {
+    /// What the overridden method returns
+    pub return_ty: Type,
+    /// Where the base is in the returned class, in bytes
+    pub offset: u64,
+}
+
+/// The class a pointer or reference type points to, going by the last segment of its path
+fn pointee_class(ty: &Type) -> Option<String> {
+    let pointee = match ty {
+        Type::Ptr(pointer) => &pointer.elem,
+        Type::Reference(reference) => &reference.elem,
+        _ => return None,
+    };
+    match &**pointee {
+        Type::Path(path) => Some(path.path.segments.last()?.ident.to_string()),
+        _ => None,
+    }
+}
+
+/// Whether an override returns a pointer or reference to a different class than the method it
+/// overrides, and where that method's class is in it.
+///
+/// Classes which aren't in the debug info are taken to be Rust types deriving from the class with
+/// `#[inherit_from]`, which puts it at the start.
+pub fn covariant_return(
+    sig: &Signature,
+    entry: &VTableElement,
+    info: &VTableInfo,
+) -> syn::Result<Option<CovariantReturn>> {
+    let base = match &entry.return_type {
+        Some(CppType::Pointer { pointee: class, .. })
+        | Some(CppType::Reference {
+            referent: class, ..
+        }) => match &**class {
+            CppType::Named(base) => base,
+            _ => return Ok(None),
+        },
+        _ => return Ok(None),
+    };
+    let output = match &sig.output {
+        ReturnType::Type(_, ty) => ty,
+        ReturnType::Default => return Ok(None),
+    };
+    let derived = match pointee_class(output) {
+        Some(derived) if derived != *base => derived,
+        _ => return Ok(None),
+    };
+
+    let offset = match info.base_offset(&derived, base) {
+        Some(BaseOffset::Fixed(offset)) => offset,
+        Some(BaseOffset::Virtual) => {
+            return Err(syn::Error::new_spanned(
+                output,
+                format!(
+                    "`{}` is a virtual base of `{}`, so returning it in place of `{}` isn't \
+                     supported",
+                    base, derived, base
+                ),
+            ))
+        }
+        None if info.bases.contains_key(&derived) => {
+            return Err(syn::Error::new_spanned(
+                output,
+                format!(
+                    "`{}` doesn't derive from `{}`, which `{}` returns",
+                    derived, base, entry.name
+                ),
+            ))
+        }
+        None => 0,
+    };
+    let return_ty = entry
+        .return_type
+        .as_ref()
+        .and_then(rust_type)
+        .expect("pointers to classes have a Rust equivalent");
+
+    Ok(Some(CovariantReturn { return_ty, offset }))
+}
+
+/// Generate the function which goes in the vtable for an override. It calls the override with
+/// the C++ calling convention, and catches any panic so it doesn't unwind into C++.
+///
+/// With `indirect_return` the return value is written through a pointer passed before `this`,
+/// which is then returned as the Itanium ABI expects. With `covariant` the returned pointer or
+/// reference is adjusted to point at the base class.
+pub fn generate_override_shim(
+    sig: &Signature,
+    on_panic: OnPanic,
+    abi: &Abi,
+    indirect_return: bool,
+    covariant: Option<&CovariantReturn>,
+) -> impl ToTokens {
+    let method = &sig.ident;
+    let ident = get_shim_ident(method);
+    let mut output = sig.output.clone();
+
+    let (receiver, this_ty) = match sig.inputs.first() {
+        Some(FnArg::Receiver(receiver)) if receiver.reference.is_some() => {
+            if receiver.mutability.is_some() {
+                (quote!(&mut self), quote!(&mut Self))
+            } else {
+                (quote!(&self), quote!(&Self))
+            }
+        }
+        _ => panic!("Overridden methods must take self by reference"),
+    };
+    // `self` has to be the first parameter, so it's renamed when the return pointer comes first
+    let this = if indirect_return {
+        quote!(this)
+    } else {
+        quote!(self)
+    };
+
+    let arg_types = sig
+        .inputs
+        .iter()
+        .skip(1)
+        .map(|arg| match arg {
+            FnArg::Typed(arg) => &arg.ty,
+            FnArg::Receiver(_) => unreachable!(),
+        })
+        .collect::<Vec<_>>();
+
+    let arg_names = (0..arg_types.len())
+        .map(|i| format_ident!("arg{}", i))
+        .collect::<Vec<_>>();
+
+    let message = format!("`{}` panicked when called from C++", method);
+    let (abi, handle_panic) = match on_panic {
+        OnPanic::Abort => (
+            abi.clone(),
+            quote!({
+                ::std::eprintln!("{}, aborting", #message);
+                ::std::process::abort()
+            }),
+        ),
+        OnPanic::Default => (
+            abi.clone(),
+            quote!({
+                ::std::eprintln!("{}, returning the default", #message);
+                ::core::default::Default::default()
+            }),
+        ),
+        // The exception has to unwind through the shim to get back to C++
+        OnPanic::Throw => (
+            unwind_abi(abi),
+            quote!(unsafe { ::cpp_inherit::throw_panic(payload) }),
+        ),
+    };
+
+    let mut call = quote!(
+        let result = ::std::panic::catch_unwind(::std::panic::AssertUnwindSafe(|| {
+            Self::#method(#this, #(#arg_names),*)
+        }));
+        let value = match result {
+            Ok(value) => value,
+            Err(payload) => #handle_panic,
+        };
+    );
+
+    // Null pointers stay null, like in C++
+    if let Some(CovariantReturn { return_ty, offset }) = covariant {
+        let offset = *offset as usize;
+        let adjust = match return_ty {
+            Type::Reference(reference) if reference.mutability.is_some() => quote!(
+                unsafe { &mut *(value.add(#offset) as *mut _) }
+            ),
+            Type::Reference(_) => quote!(
+                unsafe { &*(value.add(#offset) as *const _) }
+            ),
+            _ => quote!(
+                if value.is_null() {
+                    value as #return_ty
+                } else {
+                    value.wrapping_add(#offset) as #return_ty
+                }
+            ),
+        };
+        call.extend(quote!(
+            let value = value as *const _ as *const u8;
+            let value: #return_ty = #adjust;
+        ));
+        output = parse_quote!(-> #return_ty);
+    }
+
+    if indirect_return {
+        let return_ty = match &output {
+            ReturnType::Type(_, ty) => ty,
+            ReturnType::Default => unreachable!(),
+        };
+        return quote!(
+            #[allow(dead_code, unused_variables)]
+            #abi fn #ident(
+                __cpp_inherit_return: *mut #return_ty,
+                this: #this_ty,
+                #(#arg_names: #arg_types),*
+            ) -> *mut #return_ty {
+                #call
+                unsafe { __cpp_inherit_return.write(value) };
+                __cpp_inherit_return
+            }
+        );
+    }
+
+    quote!(
+        #[allow(dead_code, unused_variables)]
+        #abi fn #ident(#receiver, #(#arg_names: #arg_types),*) #output {
+            #call
+            value
+        }
+    )
+}
@@ -0,0 +1,349 @@
+use std::env;
+use std::path::{Path, PathBuf};
+
+use cpp_inherit_core::temp_dir::TempDir;
+use cpp_inherit_core::{metadata, read_vtable_info, CppType, VTableElement, VTableInfo};
+use quote::{format_ident, quote, ToTokens};
+use syn::{parse_quote, ExprPath, FnArg, Generics, Ident, ReturnType, Signature, Type};
+
+use crate::constructors::rust_type;
+use crate::shims::unwind_abi;
+
+pub fn get_vtable_info(header: &str, class: &str, target: &str) -> VTableInfo {
+    // rustc runs in the workspace root rather than the package's directory in a workspace
+    let package_dir = env::var_os("CARGO_MANIFEST_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| env::current_dir().unwrap());
+    let header_path = package_dir.join("src").join(header);
+
+    // Use the vtables precomputed by cpp-inherit-build if the build script has them
+    if let Some(out_dir) = env::var_os("OUT_DIR") {
+        let info = metadata::find(out_dir.as_ref(), &header_path, target);
+        if let Some(info) = info.filter(|info| info.get(class).is_some()) {
+            return info;
+        }
+    }
+
+    // Crates without a build script have no OUT_DIR, so fall back to a private temp dir, which
+    // is removed afterwards
+    match env::var_os("OUT_DIR") {
+        Some(out_dir) => read_vtable_info(&header_path, &Path::new(&out_dir).join(class), target),
+        None => {
+            let dir = TempDir::new("cpp-inherit").expect("Failed to create a temp dir");
+            read_vtable_info(&header_path, &dir.path().join(class), target)
+        }
+    }
+}
+
+/// Generate the vtable for `ty`, with `methods` in slot order, along with the
+/// `__cpp_inherit_is_vtable` check `from_base_ptr` uses.
+///
+/// Slots are plain function pointers so they're pointer sized on every target, and on ARM the
+/// linker sets the Thumb bit on the address of any Thumb function (Rust or C++) as the ARM C++
+/// ABI expects of vtable entries.
+pub fn generate_vtable_const(
+    methods: Vec<ExprPath>,
+    ty: &Type,
+    generics: &Generics,
+    address_size: u8,
+) -> impl ToTokens {
+    let method_count = methods.len();
+    let address_size = usize::from(address_size);
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+
+    // Statics can't be generic, so generic types' vtables are constants, which are duplicated
+    // wherever they're used. Their vtables are then told apart by a header before the slots, of the
+    // address of a static (which isn't generic) and the type's `TypeId`.
+    let vtable = if generics.params.is_empty() {
+        quote!(
+            // The vtable is a static so that it has a single address, which objects' vtable
+            // pointers can be compared against. Raw pointers aren't `Sync`, but the slots are
+            // never written to. It's preceded by a null RTTI pointer, as generic types' vtable
+            // checks read the pointer before the slots.
+            #[repr(C)]
+            struct VTable {
+                rtti: *const (),
+                slots: [*const (); #method_count],
+            }
+
+            unsafe impl Sync for VTable {}
+
+            static VTABLE: VTable = VTable {
+                rtti: ::core::ptr::null(),
+                slots: [
+                    #(
+                        #methods as *const (),
+                    )*
+                ],
+            };
+
+            impl #ty {
+                // One constant to convert to a pointer to reduce casting
+                //
+                // TODO: is it possible to get the bindgen vtable type? if so then no casting would
+                // be needed...
+                const VTABLE_: *const [*const (); #method_count] = &VTABLE.slots;
+
+                #[allow(dead_code)]
+                unsafe fn __cpp_inherit_is_vtable(vtable: *const ()) -> bool {
+                    vtable == Self::VTABLE_ as *const ()
+                }
+            }
+        )
+    } else {
+        quote!(
+            // `marker` is right before the slots, where C++ vtables (and other types' vtables)
+            // have their RTTI pointer, so it's safe to read from any vtable of the base class
+            #[repr(C)]
+            struct VTable {
+                type_id: ::core::any::TypeId,
+                marker: *const u8,
+                slots: [*const (); #method_count],
+            }
+
+            static MARKER: u8 = 0;
+
+            impl #impl_generics #ty #where_clause {
+                const VTABLE_: *const [*const (); #method_count] = &VTable {
+                    type_id: ::core::any::TypeId::of::<Self>(),
+                    marker: &MARKER,
+                    slots: [
+                        #(
+                            #methods as *const (),
+                        )*
+                    ],
+                }
+                .slots;
+
+                #[allow(dead_code)]
+                unsafe fn __cpp_inherit_is_vtable(vtable: *const ()) -> bool {
+                    // Only vtables with the marker have the rest of the header
+                    if *(vtable as *const *const u8).sub(1) != &MARKER as *const u8 {
+                        return false;
+                    }
+                    let header = (vtable as *const u8)
+                        .sub(::core::mem::offset_of!(VTable, slots)) as *const VTable;
+                    ::core::ptr::addr_of!((*header).type_id).read()
+                        == ::core::any::TypeId::of::<Self>()
+                }
+            }
+        )
+    };
+
+    quote!(
+        // The C++ vtable's slots have to be the same size as Rust's function pointers
+        const _: [(); #address_size] = [(); ::core::mem::size_of::<*const ()>()];
+
+        const _: () = {
+            #vtable
+        };
+    )
+}
+
+pub fn get_binding_symbol(symbol: &str) -> Ident {
+    // MSVC decorated names use characters which aren't valid in identifiers (`?`, `@`, `$`)
+    let symbol = symbol
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                c.to_string()
+            } else {
+                format!("_{:x}_", c as u32)
+            }
+        })
+        .collect::<String>();
+
+    format_ident!("__cpp_inherit_internal_{}", symbol)
+}
+
+pub fn generate_binding(symbol: &str, abi: &syn::Abi) -> impl ToTokens {
+    let ident = get_binding_symbol(symbol);
+
+    quote!(
+        #abi {
+            #[link_name = #symbol]
+            fn #ident();
+        }
+    )
+}
+
+/// The signature of a method which isn't overridden, for its `super_` helper, from the types the
+/// debug info gives it. `None` if any of them has no Rust equivalent.
+pub fn base_method_signature(
+    ident: &Ident,
+    entry: &VTableElement,
+    info: &VTableInfo,
+) -> Option<Signature> {
+    let return_type = entry.return_type.as_ref()?;
+    let params = entry
+        .params
+        .iter()
+        .map(Option::as_ref)
+        .collect::<Option<Vec<_>>>()?;
+
+    let receiver: FnArg = if entry.qualifiers.is_const {
+        parse_quote!(&self)
+    } else {
+        parse_quote!(&mut self)
+    };
+    let params = params
+        .into_iter()
+        .map(|param| {
+            let ty = rust_type(param)?;
+            // Non-trivial classes are passed through a pointer to a copy the caller owns
+            Some(match param {
+                CppType::Named(name) if info.non_trivial_classes.contains(name) => {
+                    parse_quote!(&mut #ty)
+                }
+                _ => ty,
+            })
+        })
+        .collect::<Option<Vec<Type>>>()?;
+    let arg_names = (0..params.len()).map(|i| format_ident!("arg{}", i));
+    let output: ReturnType = match return_type {
+        CppType::Void => ReturnType::Default,
+        ty => {
+            let ty = rust_type(ty)?;
+            parse_quote!(-> #ty)
+        }
+    };
+
+    Some(parse_quote!(fn #ident(#receiver, #(#arg_names: #params),*) #output))
+}
+
+/// Generate a `super_<method>` helper which calls the base class' implementation of a method by
+/// linking directly against its mangled symbol.
+///
+/// With `catch_exceptions` the call goes through `cpp_inherit::catch_cpp_exception` and
+/// the helper returns a `Result`. With `indirect_return` the implementation is passed a pointer to
+/// write the return value to.
+pub fn generate_super_call(
+    sig: &Signature,
+    symbol: &str,
+    abi: &syn::Abi,
+    catch_exceptions: bool,
+    indirect_return: bool,
+) -> impl ToTokens {
+    let ident = format_ident!("super_{}", sig.ident);
+    let binding = get_binding_symbol(symbol);
+    let output = &sig.output;
+
+    // The binding is declared inside the helper, where the type's generic parameters (and so
+    // `Self`) can't be named, so `this` is passed as an untyped reference. It's still a reference
+    // so returned references borrow from it.
+    let (receiver, this_ty, this) = match sig.inputs.first() {
+        Some(FnArg::Receiver(receiver)) if receiver.reference.is_some() => {
+            if receiver.mutability.is_some() {
+                (
+                    quote!(&mut self),
+                    quote!(&mut ::core::ffi::c_void),
+                    quote!(&mut *(self as *mut Self as *mut ::core::ffi::c_void)),
+                )
+            } else {
+                (
+                    quote!(&self),
+                    quote!(&::core::ffi::c_void),
+                    quote!(&*(self as *const Self as *const ::core::ffi::c_void)),
+                )
+            }
+        }
+        _ => panic!("Overridden methods must take self by reference"),
+    };
+
+    let arg_types = sig
+        .inputs
+        .iter()
+        .skip(1)
+        .map(|arg| match arg {
+            FnArg::Typed(arg) => &arg.ty,
+            FnArg::Receiver(_) => unreachable!(),
+        })
+        .collect::<Vec<_>>();
+
+    let arg_names = (0..arg_types.len())
+        .map(|i| format_ident!("arg{}", i))
+        .collect::<Vec<_>>();
+
+    let return_ty = match output {
+        ReturnType::Default => quote!(()),
+        ReturnType::Type(_, ty) => quote!(#ty),
+    };
+
+    if indirect_return {
+        let binding_abi = if catch_exceptions {
+            unwind_abi(abi)
+        } else {
+            abi.clone()
+        };
+        let (result_ty, call) = if catch_exceptions {
+            (
+                quote!(Result<#return_ty, ::cpp_inherit::CppException>),
+                quote!(
+                    ::cpp_inherit::catch_cpp_exception(|| {
+                        #binding(return_ptr, #this, #(#arg_names),*);
+                    })
+                    .map(|()| value.assume_init())
+                ),
+            )
+        } else {
+            (
+                quote!(#return_ty),
+                quote!(
+                    #binding(return_ptr, #this, #(#arg_names),*);
+                    value.assume_init()
+                ),
+            )
+        };
+
+        return quote!(
+            #[allow(dead_code)]
+            fn #ident(#receiver, #(#arg_names: #arg_types),*) -> #result_ty {
+                #binding_abi {
+                    #[link_name = #symbol]
+                    fn #binding(
+                        return_ptr: *mut #return_ty,
+                        this: #this_ty,
+                        #(#arg_names: #arg_types),*
+                    ) -> *mut #return_ty;
+                }
+
+                let mut value = ::core::mem::MaybeUninit::<#return_ty>::uninit();
+                let return_ptr = value.as_mut_ptr();
+                unsafe { #call }
+            }
+        );
+    }
+
+    if catch_exceptions {
+        let abi = unwind_abi(abi);
+
+        return quote!(
+            #[allow(dead_code)]
+            fn #ident(
+                #receiver,
+                #(#arg_names: #arg_types),*
+            ) -> Result<#return_ty, ::cpp_inherit::CppException> {
+                #abi {
+                    #[link_name = #symbol]
+                    fn #binding(this: #this_ty, #(#arg_names: #arg_types),*) #output;
+                }
+
+                unsafe {
+                    ::cpp_inherit::catch_cpp_exception(|| #binding(#this, #(#arg_names),*))
+                }
+            }
+        );
+    }
+
+    quote!(
+        #[allow(dead_code)]
+        fn #ident(#receiver, #(#arg_names: #arg_types),*) #output {
+            #abi {
+                #[link_name = #symbol]
+                fn #binding(this: #this_ty, #(#arg_names: #arg_types),*) #output;
+            }
+
+            unsafe { #binding(#this, #(#arg_names),*) }
+        }
+    )
+}
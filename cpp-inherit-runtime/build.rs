@@ -0,0 +1,10 @@
+fn main() {
+    #[cfg(feature = "exceptions")]
+    {
+        println!("cargo:rerun-if-changed=src/exceptions.cpp");
+        cc::Build::new()
+            .cpp(true)
+            .file("src/exceptions.cpp")
+            .compile("cpp-inherit-exceptions");
+    }
+}
@@ -0,0 +1,27 @@
+#include <cstdlib>
+#include <cstring>
+#include <exception>
+#include <stdexcept>
+
+// Rust can't throw C++ exceptions itself
+extern "C" [[noreturn]] void cpp_inherit_throw(const char* message) {
+    throw std::runtime_error(message);
+}
+
+// Nor catch them, so Rust calls C++ (which may throw) from within `call`. The message of an
+// exception which isn't a `std::exception` is null, otherwise it's freed by `cpp_inherit_free`.
+extern "C" bool cpp_inherit_try(void (*call)(void*), void* context, char** what) {
+    try {
+        call(context);
+        return true;
+    } catch (const std::exception& exception) {
+        *what = strdup(exception.what());
+    } catch (...) {
+        *what = nullptr;
+    }
+    return false;
+}
+
+extern "C" void cpp_inherit_free(char* what) {
+    std::free(what);
+}
@@ -0,0 +1,108 @@
+use alloc::boxed::Box;
+use alloc::ffi::CString;
+use alloc::string::String;
+use core::any::Any;
+use core::ffi::{c_char, c_void, CStr};
+use core::fmt;
+use core::ptr;
+
+extern "C-unwind" {
+    fn cpp_inherit_throw(message: *const c_char) -> !;
+    fn cpp_inherit_try(
+        call: extern "C-unwind" fn(*mut c_void),
+        context: *mut c_void,
+        what: *mut *mut c_char,
+    ) -> bool;
+}
+
+extern "C" {
+    fn cpp_inherit_free(what: *mut c_char);
+}
+
+/// A C++ exception caught by [`catch_cpp_exception`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CppException {
+    what: Option<String>,
+}
+
+impl CppException {
+    /// The exception's `what()` message, if it was a `std::exception`
+    pub fn what(&self) -> Option<&str> {
+        self.what.as_deref()
+    }
+}
+
+impl fmt::Display for CppException {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.what {
+            Some(what) => write!(f, "C++ exception: {}", what),
+            None => f.write_str("unknown C++ exception"),
+        }
+    }
+}
+
+/// Call C++ code which may throw, catching any exception. `inherit_from_impl` generates this
+/// around `super_` calls of methods marked `#[overridden(catch_exceptions)]`.
+///
+/// # Safety
+///
+/// `call` must not panic, as the C++ code catching exceptions would also catch the panic.
+/// Whatever it calls must use an `-unwind` ABI so exceptions can unwind through Rust.
+pub unsafe fn catch_cpp_exception<R>(call: impl FnOnce() -> R) -> Result<R, CppException> {
+    extern "C-unwind" fn trampoline<F: FnOnce() -> R, R>(context: *mut c_void) {
+        let context = unsafe { &mut *(context as *mut (Option<F>, Option<R>)) };
+        let call = context.0.take().unwrap();
+        context.1 = Some(call());
+    }
+
+    // The closure's type can't be named, so it's inferred from the context
+    fn context_trampoline<F: FnOnce() -> R, R>(
+        _: &(Option<F>, Option<R>),
+    ) -> extern "C-unwind" fn(*mut c_void) {
+        trampoline::<F, R>
+    }
+
+    let mut context = (Some(call), None);
+    let mut what = ptr::null_mut();
+    let trampoline = context_trampoline(&context);
+    if cpp_inherit_try(trampoline, &mut context as *mut _ as *mut c_void, &mut what) {
+        Ok(context.1.unwrap())
+    } else if what.is_null() {
+        Err(CppException { what: None })
+    } else {
+        let message = CStr::from_ptr(what).to_string_lossy().into_owned();
+        cpp_inherit_free(what);
+        Err(CppException {
+            what: Some(message),
+        })
+    }
+}
+
+/// Throw a `std::runtime_error` with the given message
+///
+/// # Safety
+///
+/// The caller must be able to unwind (e.g. an `extern "C-unwind"` function), and be called from
+/// C++ which catches the exception.
+pub unsafe fn throw_cpp_exception(message: &CStr) -> ! {
+    cpp_inherit_throw(message.as_ptr())
+}
+
+/// Rethrow a panic caught with `catch_unwind` as a `std::runtime_error` with the panic's message
+///
+/// # Safety
+///
+/// As for [`throw_cpp_exception`].
+pub unsafe fn throw_panic(payload: Box<dyn Any + Send>) -> ! {
+    let message = if let Some(message) = payload.downcast_ref::<&str>() {
+        String::from(*message)
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        String::from("Rust panic")
+    };
+    drop(payload);
+
+    let message = CString::new(message.replace('\0', "")).unwrap();
+    throw_cpp_exception(&message)
+}
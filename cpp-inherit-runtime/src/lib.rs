@@ -0,0 +1,220 @@
+//! Runtime support for types declared with `cpp-inherit`'s `#[inherit_from]`, which the macros'
+//! generated code refers to. `cpp-inherit` re-exports all of it, so it doesn't need to be
+//! depended on directly.
+//!
+//! [`CppBox`] owns an object allocated with C++'s `operator new`, so ownership can be handed to
+//! C++ code which later `delete`s it through a base class pointer:
+//!
+//! ```ignore
+//! let object = CppBox::new(RustType::with_base(base, ...));
+//! unsafe { take_ownership(CppBox::into_raw(object)) };
+//! ```
+#![no_std]
+
+#[cfg(feature = "exceptions")]
+extern crate alloc;
+
+#[cfg(feature = "exceptions")]
+mod exceptions;
+#[cfg(feature = "exceptions")]
+pub use exceptions::{catch_cpp_exception, throw_cpp_exception, throw_panic, CppException};
+
+use core::ffi::c_void;
+use core::fmt;
+use core::marker::PhantomData;
+use core::mem;
+use core::ops::{Deref, DerefMut};
+use core::pin::Pin;
+use core::ptr::NonNull;
+
+// `operator new` throws `std::bad_alloc` instead of returning null, so it's allowed to unwind
+extern "C-unwind" {
+    #[cfg_attr(
+        all(not(target_env = "msvc"), target_pointer_width = "64"),
+        link_name = "_Znwm"
+    )]
+    #[cfg_attr(
+        all(
+            not(target_env = "msvc"),
+            target_pointer_width = "32",
+            not(target_arch = "wasm32")
+        ),
+        link_name = "_Znwj"
+    )]
+    #[cfg_attr(
+        all(not(target_env = "msvc"), target_arch = "wasm32"),
+        link_name = "_Znwm"
+    )]
+    #[cfg_attr(
+        all(target_env = "msvc", target_pointer_width = "64"),
+        link_name = "??2@YAPEAX_K@Z"
+    )]
+    #[cfg_attr(
+        all(target_env = "msvc", target_pointer_width = "32"),
+        link_name = "??2@YAPAXI@Z"
+    )]
+    fn cpp_operator_new(size: usize) -> *mut c_void;
+}
+
+extern "C" {
+    #[cfg_attr(not(target_env = "msvc"), link_name = "_ZdlPv")]
+    #[cfg_attr(
+        all(target_env = "msvc", target_pointer_width = "64"),
+        link_name = "??3@YAXPEAX@Z"
+    )]
+    #[cfg_attr(
+        all(target_env = "msvc", target_pointer_width = "32"),
+        link_name = "??3@YAXPAX@Z"
+    )]
+    fn cpp_operator_delete(ptr: *mut c_void);
+}
+
+/// The alignment `operator new` is relied on to provide, that of `max_align_t` on common targets
+const NEW_ALIGNMENT: usize = 2 * mem::size_of::<usize>();
+
+/// Free memory allocated by C++'s `operator new`, without running any destructor
+///
+/// # Safety
+///
+/// `ptr` must be null or have been returned by `operator new`, and not freed already.
+pub unsafe fn operator_delete(ptr: *mut c_void) {
+    cpp_operator_delete(ptr)
+}
+
+/// Implemented by `#[inherit_from_impl]` for each type, which starts with its base class and has
+/// a Rust vtable. The helpers `#[inherit_from]` generates which need the vtable (`with_base`,
+/// `from_base_ptr` and the like) require it.
+///
+/// # Safety
+///
+/// `Base` must be at the start of `Self`, `VTABLE` must be a vtable for `Base` which `is_vtable`
+/// accepts, and `delete` must destroy and free an object like C++'s `delete` would.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` has no vtable",
+    note = "add an `#[inherit_from_impl]` block for `{Self}`, which generates its vtable"
+)]
+pub unsafe trait Inherits: Sized {
+    type Base;
+
+    /// The vtable objects of this type point at
+    const VTABLE: *const ();
+
+    /// Whether a vtable pointer points at this type's vtable
+    ///
+    /// # Safety
+    ///
+    /// `vtable` must be the vtable pointer of a valid `Base`.
+    unsafe fn is_vtable(vtable: *const ()) -> bool;
+
+    /// Destroy an object allocated with `operator new` and free it, through the deleting
+    /// destructor if the base class has a virtual destructor
+    ///
+    /// # Safety
+    ///
+    /// `this` must point to a live object allocated with `operator new`, which isn't used again.
+    unsafe fn delete(this: *mut Self);
+}
+
+/// An owned object allocated with C++'s `operator new`, which C++ can free with `delete`
+pub struct CppBox<T: Inherits> {
+    ptr: NonNull<T>,
+    _owned: PhantomData<T>,
+}
+
+unsafe impl<T: Inherits + Send> Send for CppBox<T> {}
+unsafe impl<T: Inherits + Sync> Sync for CppBox<T> {}
+
+fn allocate<T>() -> NonNull<T> {
+    assert!(
+        mem::align_of::<T>() <= NEW_ALIGNMENT,
+        "CppBox can't allocate types aligned to more than {} bytes",
+        NEW_ALIGNMENT
+    );
+    let ptr = unsafe { cpp_operator_new(mem::size_of::<T>().max(1)) };
+    NonNull::new(ptr as *mut T).expect("operator new returned null")
+}
+
+impl<T: Inherits> CppBox<T> {
+    /// Move `value` into memory allocated with `operator new`
+    pub fn new(value: T) -> Self {
+        let ptr = allocate::<T>();
+        unsafe {
+            ptr.as_ptr().write(value);
+            Self::from_non_null(ptr)
+        }
+    }
+
+    /// Allocate with `operator new` and construct in place with `init`, e.g. with
+    /// `|this| RustType::new_in_place(this, ...)` so the object's address never changes
+    ///
+    /// # Safety
+    ///
+    /// `init` must initialise the object.
+    pub unsafe fn new_in_place(init: impl FnOnce(*mut T)) -> Pin<Self> {
+        let ptr = allocate::<T>();
+        init(ptr.as_ptr());
+        Pin::new_unchecked(Self::from_non_null(ptr))
+    }
+
+    unsafe fn from_non_null(ptr: NonNull<T>) -> Self {
+        Self {
+            ptr,
+            _owned: PhantomData,
+        }
+    }
+
+    /// Take ownership of an object from a base class pointer, e.g. one returned by
+    /// [`CppBox::into_raw`]
+    ///
+    /// # Safety
+    ///
+    /// `base` must be the base of a live `T` allocated with `operator new`, which nothing else
+    /// owns.
+    pub unsafe fn from_raw(base: *mut T::Base) -> Self {
+        Self::from_non_null(NonNull::new_unchecked(base as *mut T))
+    }
+
+    /// Give up ownership, returning a base class pointer to pass to C++, which is responsible
+    /// for `delete`ing it
+    pub fn into_raw(this: Self) -> *mut T::Base {
+        let ptr = this.ptr.as_ptr();
+        mem::forget(this);
+        ptr as *mut T::Base
+    }
+
+    /// [`CppBox::into_raw`] for a pinned box. C++ must not move the object either.
+    pub fn into_raw_pinned(this: Pin<Self>) -> *mut T::Base {
+        Self::into_raw(unsafe { Pin::into_inner_unchecked(this) })
+    }
+
+    /// A base class pointer to pass to C++ without giving up ownership
+    pub fn as_base_ptr(this: &Self) -> *mut T::Base {
+        this.ptr.as_ptr() as *mut T::Base
+    }
+}
+
+impl<T: Inherits> Deref for CppBox<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { self.ptr.as_ref() }
+    }
+}
+
+impl<T: Inherits> DerefMut for CppBox<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { self.ptr.as_mut() }
+    }
+}
+
+impl<T: Inherits> Drop for CppBox<T> {
+    fn drop(&mut self) {
+        unsafe { T::delete(self.ptr.as_ptr()) }
+    }
+}
+
+impl<T: Inherits + fmt::Debug> fmt::Debug for CppBox<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
@@ -0,0 +1,8 @@
+fn main() {
+    println!("cargo:rerun-if-changed=src/classes.hpp");
+    println!("cargo:rerun-if-changed=src/classes.cpp");
+    cc::Build::new()
+        .cpp(true)
+        .file("src/classes.cpp")
+        .compile("cpp-inherit-tests");
+}
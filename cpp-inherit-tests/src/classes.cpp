@@ -0,0 +1,81 @@
+#include "classes.hpp"
+
+#include <new>
+#include <stdexcept>
+#include <string>
+
+static int destroyed;
+
+base::base(int value) : value(value) {}
+base::~base() { destroyed++; }
+int base::x() { return value; }
+
+extern "C" int call_x(base* object) { return object->x(); }
+extern "C" void delete_base(base* object) { delete object; }
+extern "C" int destroyed_bases() { return destroyed; }
+extern "C" base* new_base(int value) { return new base(value); }
+
+static std::string exception;
+
+extern "C" int call_x_catching(base* object) {
+    try {
+        return object->x();
+    } catch (const std::exception& error) {
+        exception = error.what();
+        return -1;
+    }
+}
+extern "C" const char* last_exception() { return exception.c_str(); }
+
+overloaded::~overloaded() {}
+int overloaded::f(int value) { return value; }
+int overloaded::f(double value) { return (int)(value * 10); }
+int overloaded::f(int a, int b) { return a + b; }
+int overloaded::f(long value) { return (int)value * 100; }
+
+extern "C" int call_f_int(overloaded* object, int value) { return object->f(value); }
+extern "C" int call_f_double(overloaded* object, double value) { return object->f(value); }
+extern "C" int call_f_pair(overloaded* object, int a, int b) { return object->f(a, b); }
+extern "C" int call_f_long(overloaded* object, long value) { return object->f(value); }
+
+static int live;
+
+counted::counted(int value) : value(value) { live++; }
+counted::counted(const counted& other) : value(other.value) { live++; }
+counted::~counted() { live--; }
+
+holder::holder(counted c) : value(c.value) {}
+holder::~holder() {}
+int holder::get() { return value; }
+
+extern "C" void make_counted(counted* object, int value) { new (object) counted(value); }
+extern "C" void destroy_counted(counted* object) { object->~counted(); }
+extern "C" int live_counted() { return live; }
+extern "C" int call_get(holder* object) { return object->get(); }
+
+thrower::~thrower() {}
+int thrower::risky(int value) {
+    if (value > 10) {
+        throw std::runtime_error("too big");
+    }
+    return value;
+}
+int thrower::safe() { return 1; }
+
+animal::~animal() {}
+animal* animal::clone() const { return new animal(*this); }
+const animal& animal::me() const { return *this; }
+tagged::~tagged() {}
+int tagged::tag() { return t; }
+
+extern "C" pet* make_pet(int legs) {
+    pet* object = new pet();
+    object->t = -1;
+    object->legs = legs;
+    return object;
+}
+extern "C" void delete_pet(pet* object) { delete object; }
+extern "C" void delete_animal(animal* object) { delete object; }
+extern "C" int clone_legs(animal* object) { return object->clone()->legs; }
+extern "C" bool clone_is_null(animal* object) { return object->clone() == nullptr; }
+extern "C" int me_legs(animal* object) { return object->me().legs; }
@@ -0,0 +1,82 @@
+// Classes the tests inherit from, with helpers calling into them from C++
+
+struct base {
+    int value;
+    base(int value);
+    virtual ~base();
+    virtual int x();
+};
+
+extern "C" int call_x(base* object);
+extern "C" void delete_base(base* object);
+extern "C" int destroyed_bases();
+extern "C" base* new_base(int value);
+// Returns -1 if `x` throws, keeping the exception's message for `last_exception`
+extern "C" int call_x_catching(base* object);
+extern "C" const char* last_exception();
+
+// Overloaded on its parameters rather than its qualifiers
+struct overloaded {
+    virtual ~overloaded();
+    virtual int f(int value);
+    virtual int f(double value);
+    virtual int f(int a, int b);
+    virtual int f(long value);
+};
+
+extern "C" int call_f_int(overloaded* object, int value);
+extern "C" int call_f_double(overloaded* object, double value);
+extern "C" int call_f_pair(overloaded* object, int a, int b);
+extern "C" int call_f_long(overloaded* object, long value);
+
+// Not trivial for the purposes of calls, so passed through a pointer to a copy
+struct counted {
+    int value;
+    counted(int value);
+    counted(const counted& other);
+    ~counted();
+};
+
+struct holder {
+    int value;
+    holder(counted c);
+    virtual ~holder();
+    virtual int get();
+};
+
+extern "C" void make_counted(counted* object, int value);
+extern "C" void destroy_counted(counted* object);
+extern "C" int live_counted();
+extern "C" int call_get(holder* object);
+
+// Throws from its implementation of `risky`
+struct thrower {
+    virtual ~thrower();
+    virtual int risky(int value);
+    virtual int safe();
+};
+
+// `animal` is `pet`'s second base, so a `pet*` has to be adjusted to be an `animal*`
+struct animal {
+    virtual ~animal();
+    virtual animal* clone() const;
+    virtual const animal& me() const;
+    int legs;
+};
+
+struct tagged {
+    virtual ~tagged();
+    virtual int tag();
+    int t;
+};
+
+struct pet : tagged, animal {
+    int name;
+};
+
+extern "C" pet* make_pet(int legs);
+extern "C" void delete_pet(pet* object);
+extern "C" void delete_animal(animal* object);
+extern "C" int clone_legs(animal* object);
+extern "C" bool clone_is_null(animal* object);
+extern "C" int me_legs(animal* object);
@@ -0,0 +1,68 @@
+//! Bindings to the classes in `classes.hpp`, which the tests in `tests/` inherit from. Base class
+//! layouts are written out like bindgen's.
+#![allow(non_camel_case_types)]
+
+#[repr(C)]
+pub struct base {
+    pub vtable_: *const (),
+    pub value: i32,
+}
+
+#[repr(C)]
+pub struct overloaded {
+    pub vtable_: *const (),
+}
+
+#[repr(C)]
+pub struct counted {
+    pub value: i32,
+}
+
+#[repr(C)]
+pub struct holder {
+    pub vtable_: *const (),
+    pub value: i32,
+}
+
+#[repr(C)]
+pub struct thrower {
+    pub vtable_: *const (),
+}
+
+#[repr(C)]
+pub struct animal {
+    pub vtable_: *const (),
+    pub legs: i32,
+}
+
+/// Only used through pointers
+#[repr(C)]
+pub struct pet {
+    _private: [u8; 0],
+}
+
+extern "C" {
+    pub fn call_x(object: *mut base) -> i32;
+    pub fn delete_base(object: *mut base);
+    pub fn destroyed_bases() -> i32;
+    pub fn new_base(value: i32) -> *mut base;
+    pub fn call_x_catching(object: *mut base) -> i32;
+    pub fn last_exception() -> *const core::ffi::c_char;
+
+    pub fn call_f_int(object: *mut overloaded, value: i32) -> i32;
+    pub fn call_f_double(object: *mut overloaded, value: f64) -> i32;
+    pub fn call_f_pair(object: *mut overloaded, a: i32, b: i32) -> i32;
+    pub fn call_f_long(object: *mut overloaded, value: core::ffi::c_long) -> i32;
+
+    pub fn make_counted(object: *mut counted, value: i32);
+    pub fn destroy_counted(object: *mut counted);
+    pub fn live_counted() -> i32;
+    pub fn call_get(object: *mut holder) -> i32;
+
+    pub fn make_pet(legs: i32) -> *mut pet;
+    pub fn delete_pet(object: *mut pet);
+    pub fn delete_animal(object: *mut animal);
+    pub fn clone_legs(object: *mut animal) -> i32;
+    pub fn clone_is_null(object: *mut animal) -> bool;
+    pub fn me_legs(object: *mut animal) -> i32;
+}
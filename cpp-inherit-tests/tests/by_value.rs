@@ -0,0 +1,32 @@
+//! Classes which aren't trivial for the purposes of calls, passed by value
+
+use core::mem::MaybeUninit;
+use cpp_inherit::*;
+use cpp_inherit_tests::*;
+
+#[inherit_from(holder)]
+struct Holder {}
+
+#[inherit_from_impl(holder, "classes.hpp")]
+impl Holder {
+    #[overridden]
+    fn get(&mut self) -> i32 {
+        self.super_get() + 1
+    }
+}
+
+#[test]
+fn constructor_taking_a_copy() {
+    let live = unsafe { live_counted() };
+
+    // The constructor takes a pointer to the caller's copy, which the caller destroys
+    let mut copy = MaybeUninit::<counted>::uninit();
+    unsafe { make_counted(copy.as_mut_ptr(), 42) };
+    let copy = unsafe { copy.assume_init_mut() };
+    let mut object = Holder::new_boxed(|base| unsafe { Holder::construct_base(base, copy) });
+    unsafe { destroy_counted(copy) };
+    assert_eq!(unsafe { live_counted() }, live);
+
+    assert_eq!(object.value, 42);
+    assert_eq!(unsafe { call_get(&mut **object) }, 43);
+}
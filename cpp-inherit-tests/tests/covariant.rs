@@ -0,0 +1,55 @@
+//! Overrides returning a class deriving from the one the C++ method returns
+
+use cpp_inherit::*;
+use cpp_inherit_tests::*;
+
+#[inherit_from(animal)]
+struct Owner {
+    pet: *mut pet,
+}
+
+#[inherit_from_impl(animal, "classes.hpp")]
+impl Owner {
+    fn new(pet: *mut pet) -> Self {
+        Self::with_base(
+            animal {
+                vtable_: core::ptr::null(),
+                legs: 2,
+            },
+            pet,
+        )
+    }
+
+    // `animal` isn't at the start of `pet`, so these are adjusted like C++'s return thunks would
+    #[overridden]
+    fn clone(&self) -> *mut pet {
+        self.pet
+    }
+
+    #[overridden]
+    fn me(&self) -> &pet {
+        unsafe { &*self.pet }
+    }
+}
+
+#[test]
+fn second_base_is_adjusted() {
+    let pet = unsafe { make_pet(4) };
+    let mut owner = Owner::new(pet);
+    let base = &mut *owner as *mut animal;
+    assert_eq!(unsafe { clone_legs(base) }, 4);
+    assert_eq!(unsafe { me_legs(base) }, 4);
+
+    // The base class' implementation still returns an `animal*`
+    let clone = owner.super_clone();
+    assert_eq!(unsafe { (*clone).legs }, 2);
+    unsafe { delete_animal(clone) };
+
+    unsafe { delete_pet(pet) };
+}
+
+#[test]
+fn null_stays_null() {
+    let mut owner = Owner::new(core::ptr::null_mut());
+    assert!(unsafe { clone_is_null(&mut *owner) });
+}
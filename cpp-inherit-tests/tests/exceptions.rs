@@ -0,0 +1,33 @@
+//! C++ exceptions thrown by the base class' implementation, caught by `super_` helpers
+
+use cpp_inherit::*;
+use cpp_inherit_tests::*;
+
+#[inherit_from(thrower)]
+struct Catcher {}
+
+// `risky` isn't overridden, but its helper catches exceptions too
+#[inherit_from_impl(thrower, "classes.hpp", catch_exceptions)]
+impl Catcher {
+    fn new() -> Self {
+        Self::with_base(thrower {
+            vtable_: core::ptr::null(),
+        })
+    }
+
+    #[overridden]
+    fn safe(&mut self) -> i32 {
+        self.super_safe().unwrap() + 1
+    }
+}
+
+#[test]
+fn super_helper_of_a_method_which_isnt_overridden() {
+    let mut object = Catcher::new();
+    assert_eq!(object.super_risky(3).unwrap(), 3);
+
+    let exception = object.super_risky(11).unwrap_err();
+    assert_eq!(exception.what(), Some("too big"));
+
+    assert_eq!(object.safe(), 2);
+}
@@ -0,0 +1,93 @@
+//! Generic types, whose vtables are told apart by their marker and `TypeId`
+
+use cpp_inherit::*;
+use cpp_inherit_tests::*;
+
+trait Payload {
+    fn weight(&self) -> i32;
+}
+
+impl Payload for i32 {
+    fn weight(&self) -> i32 {
+        *self
+    }
+}
+
+impl Payload for u8 {
+    fn weight(&self) -> i32 {
+        i32::from(*self) * 10
+    }
+}
+
+#[inherit_from(base)]
+struct Node<T: Payload + 'static> {
+    payload: T,
+}
+
+#[inherit_from_impl(base, "classes.hpp")]
+impl<T: Payload + 'static> Node<T> {
+    fn new(payload: T) -> Self {
+        Self::with_base(
+            base {
+                vtable_: core::ptr::null(),
+                value: 0,
+            },
+            payload,
+        )
+    }
+
+    #[overridden]
+    fn x(&mut self) -> i32 {
+        self.payload.weight()
+    }
+}
+
+#[inherit_from(base)]
+struct Plain {}
+
+#[inherit_from_impl(base, "classes.hpp")]
+impl Plain {
+    fn new() -> Self {
+        Self::with_base(base {
+            vtable_: core::ptr::null(),
+            value: 0,
+        })
+    }
+}
+
+#[test]
+fn instantiations_have_their_own_vtables() {
+    let mut int = Node::new(3i32);
+    let mut byte = Node::new(4u8);
+    assert_eq!(unsafe { call_x(&mut *int) }, 3);
+    assert_eq!(unsafe { call_x(&mut *byte) }, 40);
+}
+
+#[test]
+fn from_base_ptr_checks_the_instantiation() {
+    let mut int = Node::new(3i32);
+    let mut byte = Node::new(4u8);
+    let int = &mut *int as *mut base;
+    let byte = &mut *byte as *mut base;
+
+    let found = unsafe { Node::<i32>::from_base_ptr(int) };
+    assert_eq!(found.map(|node| node.payload), Some(3));
+    assert!(unsafe { Node::<i32>::from_base_ptr(byte) }.is_none());
+    assert!(unsafe { Node::<u8>::from_base_ptr(int) }.is_none());
+    assert!(unsafe { Node::<i32>::from_base_ptr(core::ptr::null_mut()) }.is_none());
+}
+
+#[test]
+fn from_base_ptr_rejects_other_vtables() {
+    // A non-generic Rust type's vtable has a null pointer where generic ones have the marker
+    let mut plain = Plain::new();
+    let plain = &mut *plain as *mut base;
+    assert!(unsafe { Node::<i32>::from_base_ptr(plain) }.is_none());
+    assert!(unsafe { Plain::from_base_ptr(plain) }.is_some());
+
+    // C++'s vtable has the typeinfo there
+    let cpp = unsafe { new_base(5) };
+    assert!(unsafe { Node::<i32>::from_base_ptr(cpp) }.is_none());
+    assert!(unsafe { Plain::from_base_ptr(cpp) }.is_none());
+    unsafe { delete_base(cpp) };
+}
@@ -0,0 +1,87 @@
+//! The struct and its `inherit_from_impl` block in different modules
+
+use cpp_inherit_tests::*;
+
+mod types {
+    use cpp_inherit::*;
+    use cpp_inherit_tests::base;
+
+    // Only nameable in this module
+    pub struct Helper(pub i32);
+
+    #[inherit_from(base)]
+    pub struct Counter {
+        helper: Helper,
+    }
+
+    impl Counter {
+        pub fn helper(&self) -> i32 {
+            self.helper.0
+        }
+    }
+
+    // Without an `inherit_from_impl` block the struct can still be used
+    #[inherit_from(base)]
+    pub struct Plain {}
+
+    pub fn plain(value: i32) -> Plain {
+        Plain {
+            _base: base {
+                vtable_: core::ptr::null(),
+                value,
+            },
+        }
+    }
+
+    pub fn new(value: i32, helper: i32) -> Counter {
+        Counter::with_base(
+            base {
+                vtable_: core::ptr::null(),
+                value,
+            },
+            Helper(helper),
+        )
+    }
+}
+
+mod overrides {
+    use super::types::Counter;
+    use cpp_inherit::*;
+    use cpp_inherit_tests::base;
+
+    #[inherit_from_impl(base, "classes.hpp")]
+    impl Counter {
+        #[overridden]
+        fn x(&mut self) -> i32 {
+            self.super_x() * 100 + self.helper()
+        }
+    }
+}
+
+#[test]
+fn impl_in_another_module() {
+    let mut counter = types::new(3, 4);
+    assert_eq!(unsafe { call_x(&mut *counter) }, 304);
+
+    let base = &mut *counter as *mut base;
+    let counter = unsafe { types::Counter::from_base_ptr(base) }.unwrap();
+    assert_eq!(counter.helper(), 4);
+}
+
+#[test]
+fn constructed_outside_the_module() {
+    let before = unsafe { destroyed_bases() };
+    let mut counter = types::Counter::new_boxed(
+        |base| unsafe { types::Counter::construct_base(base, 5) },
+        types::Helper(6),
+    );
+    assert_eq!(unsafe { call_x(&mut **counter) }, 506);
+
+    drop(counter);
+    assert_eq!(unsafe { destroyed_bases() }, before + 1);
+}
+
+#[test]
+fn without_inherit_from_impl() {
+    assert_eq!(types::plain(7).value, 7);
+}
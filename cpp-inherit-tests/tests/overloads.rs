@@ -0,0 +1,50 @@
+//! Overrides of methods overloaded on their parameters
+
+use core::ffi::c_long;
+use cpp_inherit::*;
+use cpp_inherit_tests::*;
+
+#[inherit_from(overloaded)]
+struct Overrides {}
+
+#[inherit_from_impl(overloaded, "classes.hpp")]
+impl Overrides {
+    fn new() -> Self {
+        Self::with_base(overloaded {
+            vtable_: core::ptr::null(),
+        })
+    }
+
+    // Picked by the parameter's type, from `f(int)`, `f(double)` and `f(long)`
+    #[overridden]
+    fn f(&mut self, value: f64) -> i32 {
+        self.super_f(value) * 2
+    }
+
+    // Picked by the number of parameters
+    #[overridden(name = "f")]
+    fn f_pair(&mut self, a: i32, b: i32) -> i32 {
+        self.super_f_pair(a, b) * 10
+    }
+
+    // `c_long` isn't written like `f(long)`'s `i64`, so it's picked by its symbol
+    #[overridden(name = "f", symbol = "_ZN10overloaded1fEl")]
+    fn f_long(&mut self, value: c_long) -> i32 {
+        self.super_f_long(value) + 1
+    }
+}
+
+#[test]
+fn overloads_by_parameters() {
+    let mut object = Overrides::new();
+    let base = &mut *object as *mut overloaded;
+    unsafe {
+        assert_eq!(call_f_double(base, 1.5), 30);
+        assert_eq!(call_f_pair(base, 2, 3), 50);
+        assert_eq!(call_f_long(base, 2), 201);
+
+        // The overload which isn't overridden keeps the base class' implementation
+        assert_eq!(call_f_int(base, 7), 7);
+    }
+    assert_eq!(object.super_f_1(7), 7);
+}
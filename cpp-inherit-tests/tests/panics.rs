@@ -0,0 +1,109 @@
+//! Overrides panicking when called from C++
+
+use std::env;
+use std::ffi::CStr;
+use std::process::Command;
+
+use cpp_inherit::*;
+use cpp_inherit_tests::*;
+
+#[inherit_from(base)]
+struct Throws {}
+
+#[inherit_from_impl(base, "classes.hpp", on_panic = "throw")]
+impl Throws {
+    fn new() -> Self {
+        Self::with_base(base {
+            vtable_: core::ptr::null(),
+            value: 0,
+        })
+    }
+
+    #[overridden]
+    fn x(&mut self) -> i32 {
+        panic!("thrown from Rust")
+    }
+}
+
+#[inherit_from(base)]
+struct Defaults {}
+
+#[inherit_from_impl(base, "classes.hpp")]
+impl Defaults {
+    fn new() -> Self {
+        Self::with_base(base {
+            vtable_: core::ptr::null(),
+            value: 0,
+        })
+    }
+
+    #[overridden(on_panic = "default")]
+    fn x(&mut self) -> i32 {
+        panic!("not thrown")
+    }
+}
+
+#[inherit_from(base)]
+struct Aborts {}
+
+#[inherit_from_impl(base, "classes.hpp")]
+impl Aborts {
+    fn new() -> Self {
+        Self::with_base(base {
+            vtable_: core::ptr::null(),
+            value: 0,
+        })
+    }
+
+    #[overridden]
+    fn x(&mut self) -> i32 {
+        panic!("aborting")
+    }
+}
+
+#[test]
+fn throw_is_caught_by_cpp() {
+    let mut object = Throws::new();
+    assert_eq!(unsafe { call_x_catching(&mut *object) }, -1);
+    let what = unsafe { CStr::from_ptr(last_exception()) };
+    assert_eq!(what.to_str(), Ok("thrown from Rust"));
+}
+
+#[test]
+fn default_is_returned() {
+    let mut object = Defaults::new();
+    assert_eq!(unsafe { call_x_catching(&mut *object) }, 0);
+}
+
+/// Only run by `abort_ends_the_process`, in a process of its own
+#[test]
+#[ignore]
+fn abort_child() {
+    if env::var_os("CPP_INHERIT_ABORT_CHILD").is_none() {
+        return;
+    }
+    let mut object = Aborts::new();
+    unsafe { call_x_catching(&mut *object) };
+}
+
+#[test]
+fn abort_ends_the_process() {
+    let output = Command::new(env::current_exe().unwrap())
+        .args(["abort_child", "--exact", "--ignored", "--nocapture"])
+        .env("CPP_INHERIT_ABORT_CHILD", "1")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        assert_eq!(output.status.signal(), Some(6));
+    }
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("`x` panicked when called from C++, aborting"),
+        "{}",
+        stderr
+    );
+}
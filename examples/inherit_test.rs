@@ -1,3 +1,6 @@
+// bindgen output doesn't document the safety of its unsafe methods
+#![allow(clippy::missing_safety_doc)]
+
 use cpp_inherit::*;
 
 include!("test.rs");
@@ -6,15 +9,14 @@ include!("test.rs");
 #[derive(Debug)]
 struct Test {}
 
-#[inherit_from_impl(base, "test.hpp")]
+#[inherit_from_impl(base, "../examples/test.hpp")]
 impl Test {
     fn new() -> Self {
-        Self {
-            _base: base {
-                vtable_: Test::VTABLE_ as _,
-                value: 3,
-            },
-        }
+        // The vtable pointer is set by `with_base`
+        Self::with_base(base {
+            value: 3,
+            ..unsafe { std::mem::zeroed() }
+        })
     }
 
     #[overridden]